@@ -10,6 +10,7 @@ Values:
     t | type (program type)
     d | description
     T | tags
+    l | license (SPDX id)
 
 Example:
     # Will only show the fields Name, Project, and Tags
@@ -93,6 +94,7 @@ pub enum ListOpts {
     Type,
     Description,
     Tags,
+    License,
 }
 
 fn parse_filter(inp: &str) -> Result<FilterOpt, String> {
@@ -121,6 +123,7 @@ fn parse_filter(inp: &str) -> Result<FilterOpt, String> {
         "t" | "type" => ListOpts::Type,
         "d" | "description" => ListOpts::Description,
         "T" | "tag" | "tags" => ListOpts::Tags,
+        "l" | "license" => ListOpts::License,
         f => return Err(format!("'{}' is not a valid option for 'field'", f)),
     };
 
@@ -175,6 +178,10 @@ mod tests {
             Ok(FilterOpt(ListOpts::Path, 3, String::from("some"))),
             parse_filter("some:nin:p")
         );
+        assert_eq!(
+            Ok(FilterOpt(ListOpts::License, 0, String::from("MIT"))),
+            parse_filter("MIT:eq:license")
+        );
         assert_eq!(
             Err(String::from(
                 "Input must be in format value:expression:field"
@@ -2,12 +2,14 @@ mod list;
 
 pub use list::*;
 
-use std::{iter::Filter, path::PathBuf};
+use std::{iter::Filter, path::PathBuf, str::FromStr};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand};
 use prettytable::{row, Cell, Row, Table};
 
 use crate::error::{BoilError, BoilResult};
+use crate::project::{gitignore, license};
+use crate::storage::Format;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None, after_help="")]
@@ -19,7 +21,11 @@ pub struct Cli {
     pub debug: bool,
 
     #[arg(long, hide=true)]
-    pub cfg_path: Option<PathBuf>
+    pub cfg_path: Option<PathBuf>,
+
+    /// Profile to use instead of the one chosen with 'boil profile use'
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand, Debug, PartialEq)]
@@ -39,8 +45,46 @@ pub enum Commands {
     /// Remove a program from the configuration
     Remove(RemoveArgs),
 
+    /// Open an entry in your editor
+    Open(OpenArgs),
+
+    /// Change the program type of existing entries
+    Retype(RetypeArgs),
+
+    /// Print or write a .gitignore composed from bundled snippets
+    Gitignore(GitignoreArgs),
+
+    /// Manage the templates used by 'boil new --template'
+    Template(TemplateArgs),
+
     /// Initialize new configuration
     Init(InitArgs),
+
+    /// Upgrade a config written by an older version of boil
+    Migrate(MigrateArgs),
+
+    /// Roll the config back to a previous version
+    Restore(RestoreArgs),
+
+    /// Manage the config file itself
+    Config(ConfigArgs),
+
+    /// Manage profiles - separate configs, each with its own entries and paths
+    Profile(ProfileArgs),
+}
+
+impl Commands {
+    /// Whether running the command can change the config, so it needs to be written afterwards
+    pub fn modifies_config(&self) -> bool {
+        !matches!(
+            self,
+            Commands::List(_)
+                | Commands::Open(_)
+                | Commands::Gitignore(_)
+                | Commands::Template(_)
+                | Commands::New(NewArgs { dry_run: true, .. })
+        )
+    }
 }
 
 #[derive(Args, Debug, PartialEq)]
@@ -56,9 +100,17 @@ pub struct AddArgs {
     pub tags: Option<Vec<String>>,
 
     /// Program or project type - ie python, rust, etc.
+    ///
+    /// Detected from the file extension, shebang or project files if not given
     #[arg(short = 'T', long = "type")]
     pub prog_type: Option<String>,
 
+    /// Write (or extend) the project's .gitignore from the given snippets, or its type when none are given
+    ///
+    /// Example: boil add --gitignore=rust,vscode my-project /path/to/project
+    #[arg(long, num_args = 0..=1, require_equals = true, value_delimiter = ',', value_parser = parse_gitignore)]
+    pub gitignore: Option<Vec<String>>,
+
     /// Name of the project
     pub name: String,
 
@@ -66,7 +118,7 @@ pub struct AddArgs {
     pub path: PathBuf,
 }
 
-#[derive(Args, Debug, PartialEq, Clone)]
+#[derive(Args, Debug, PartialEq, Clone, Default)]
 pub struct NewArgs {
     /// Create a temp file/directory
     #[arg(short, long)]
@@ -94,6 +146,74 @@ pub struct NewArgs {
     #[arg(short = 'G', long, value_delimiter = ',')]
     pub tags: Option<Vec<String>>,
 
+    /// Name of a template in the template directory to copy into the new project,
+    /// or the url/path of a git repository to use as the template
+    ///
+    /// Example: boil new -D --template=service my-service
+    ///
+    /// Example: boil new -D --template=file:///srv/templates/svc.git my-service
+    #[arg(long, requires = "project")]
+    pub template: Option<String>,
+
+    /// Branch, tag or commit to use when the template is a git repository
+    #[arg(long, requires = "template")]
+    pub template_ref: Option<String>,
+
+    /// Set a template variable, answering the template's prompt of the same name if it has one.
+    /// Can be given multiple times
+    ///
+    /// Example: boil new -D --template service --set docker=yes --set port=8080 my-service
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_key_val)]
+    pub set: Vec<(String, String)>,
+
+    /// Create a library instead of a binary when the project type supports both (ie rust)
+    #[arg(long, requires = "project")]
+    pub lib: bool,
+
+    /// Initialize a git repository in the new project
+    ///
+    /// Can be turned on for every project with `git = true` under [defaults] in the config
+    #[arg(long, requires = "project", conflicts_with = "no_git")]
+    pub git: bool,
+
+    /// Also commit the generated files to the new repository (implies --git)
+    #[arg(long, requires = "project", conflicts_with = "no_git")]
+    pub commit: bool,
+
+    /// Do not initialize a git repository, even if enabled in the config
+    #[arg(long)]
+    pub no_git: bool,
+
+    /// SPDX id of a license to write to LICENSE - ie MIT, Apache-2.0, GPL-3.0-only, BSD-3-Clause
+    #[arg(long, requires = "project", value_parser = parse_license)]
+    pub license: Option<String>,
+
+    /// Compose the project's .gitignore from bundled snippets for languages, editors and systems
+    ///
+    /// Example: boil new -D --gitignore=rust,vscode,linux my-project
+    #[arg(long, requires = "project", value_delimiter = ',', value_parser = parse_gitignore)]
+    pub gitignore: Option<Vec<String>>,
+
+    /// Symlink the new script into the link directory (~/.local/bin by default) so it is on PATH
+    #[arg(long, conflicts_with_all = ["project", "temp", "no_link"])]
+    pub link: bool,
+
+    /// Do not link the new script, even if enabled in the config
+    #[arg(long)]
+    pub no_link: bool,
+
+    /// Leave the extension off of the linked script's name
+    #[arg(long, conflicts_with = "project")]
+    pub strip_ext: bool,
+
+    /// Show the files, hooks and config entry that would be created without creating them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Open the new entry in your editor once it is created
+    #[arg(long, conflicts_with = "dry_run")]
+    pub open: bool,
+
     /// Name of the project. Not required if creating a temp program
     #[arg(required_unless_present = "temp")]
     pub name: Option<String>,
@@ -134,6 +254,10 @@ pub struct EditOptsGroup {
     /// Edit program type of entry
     #[arg(short, long)]
     pub prog_type: Option<String>,
+
+    /// Record the SPDX id of the entry's license (does not write a LICENSE file)
+    #[arg(short, long, value_parser = parse_license)]
+    pub license: Option<String>,
 }
 
 #[derive(Args, Debug, PartialEq)]
@@ -142,10 +266,188 @@ pub struct RemoveArgs {
     #[arg(long, short)]
     pub force: bool,
 
-    /// Name of entry to remove (will not delete any files, only entry)
+    /// Name of entry to remove (will not delete any files, only entry and its link)
     pub name: String,
 }
 
+#[derive(Args, Debug, PartialEq)]
+pub struct OpenArgs {
+    /// Name of the entry to open
+    pub name: String,
+}
+
+#[derive(Args, Debug, PartialEq)]
+#[command(group(ArgGroup::new("retype").required(true).args(["detect", "prog_type"])))]
+pub struct RetypeArgs {
+    /// Detect the type of each entry again from its files
+    #[arg(long)]
+    pub detect: bool,
+
+    /// Set the type of the named entries
+    #[arg(short = 'T', long = "type", requires = "names")]
+    pub prog_type: Option<String>,
+
+    /// Entries to retype - all entries when detecting and none are given
+    pub names: Vec<String>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+#[command(group(ArgGroup::new("gitignore").required(true).args(["list", "snippets"])))]
+pub struct GitignoreArgs {
+    /// List the available snippets
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// Write (or extend) the .gitignore of this project entry instead of printing it
+    #[arg(short, long, conflicts_with = "list")]
+    pub entry: Option<String>,
+
+    /// Snippets to combine - languages (rust, python, node, ...), editors (vscode, vim, ...)
+    /// and systems (linux, macos, windows)
+    #[arg(value_parser = parse_gitignore)]
+    pub snippets: Vec<String>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct TemplateArgs {
+    #[command(subcommand)]
+    pub command: TemplateCommands,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum TemplateCommands {
+    /// List the templates in the template directory
+    List,
+
+    /// Show a template's files, variables and hooks
+    Show {
+        /// Name of the template, or a git repository/directory
+        name: String,
+    },
+
+    /// Copy a directory into the template directory as a new template
+    Add {
+        /// Name of the new template
+        name: String,
+
+        /// Directory to copy the files from
+        dir: PathBuf,
+    },
+
+    /// Snapshot a project entry as a new template, replacing its name with placeholders
+    Capture {
+        /// Name of the project entry in the config
+        entry: String,
+
+        /// Name of the new template
+        name: String,
+    },
+
+    /// Delete a template from the template directory
+    Remove {
+        /// Force removal without prompting
+        #[arg(long, short)]
+        force: bool,
+
+        /// Name of the template to delete
+        name: String,
+    },
+
+    /// Copy a template out of the template directory, ie to share it. Templates it extends are applied to the copy
+    Export {
+        /// Name of the template
+        name: String,
+
+        /// Directory to copy the template to - must not exist yet
+        dest: PathBuf,
+    },
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct MigrateArgs {
+    /// Only report what would change, leaving the config as is
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Args, Debug, PartialEq)]
+#[command(group(ArgGroup::new("restore").required(true).args(["list", "backup"])))]
+pub struct RestoreArgs {
+    /// List the backups, newest first
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// Number of the backup to restore, as shown by --list (1 is the newest)
+    pub backup: Option<usize>,
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigCommands {
+    /// Rewrite the config in another format, renaming it to that format's extension.
+    /// The original is kept next to it as <file>.bak
+    ///
+    /// Example: boil config convert --to json
+    Convert {
+        /// Format to convert to - toml, json or yaml
+        #[arg(long, value_parser = parse_format)]
+        to: Format,
+
+        /// Write the converted config to this file instead, leaving the config as is
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args, Debug, PartialEq)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub command: ProfileCommands,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ProfileCommands {
+    /// List the profiles, marking the one in use
+    List,
+
+    /// Create a new profile with an empty config
+    ///
+    /// Example: boil profile create --path ~/work --use work
+    Create {
+        /// Specify project directory path
+        #[arg(long, short)]
+        path: Option<PathBuf>,
+
+        /// Use the new profile from now on
+        #[arg(long = "use")]
+        switch: bool,
+
+        /// Name of the new profile
+        name: String,
+    },
+
+    /// Use a profile from now on - 'default' goes back to the original config
+    Use {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Delete a profile along with all of its entries
+    Remove {
+        /// Force removal without prompting
+        #[arg(long, short)]
+        force: bool,
+
+        /// Name of the profile to delete
+        name: String,
+    },
+}
+
 #[derive(Args, Debug, PartialEq)]
 pub struct InitArgs {
     /// Force creation/override of config file (USE WITH CAUTION!!)
@@ -160,6 +462,29 @@ pub struct InitArgs {
     pub test: bool
 }
 
+fn parse_license(inp: &str) -> Result<String, String> {
+    license::find(inp)
+        .map(|(id, _)| id.to_owned())
+        .map_err(|e| e.to_string())
+}
+
+fn parse_key_val(inp: &str) -> Result<(String, String), String> {
+    match inp.split_once('=') {
+        Some((key, val)) if !key.trim().is_empty() => Ok((key.trim().to_owned(), val.to_owned())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", inp)),
+    }
+}
+
+fn parse_format(inp: &str) -> Result<Format, String> {
+    Format::from_str(inp).map_err(|e| e.to_string())
+}
+
+fn parse_gitignore(inp: &str) -> Result<String, String> {
+    gitignore::find(inp)
+        .map(|(id, _)| id.to_owned())
+        .map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use std::{ops::Add, str::FromStr};
@@ -189,6 +514,7 @@ mod tests {
                 description,
                 tags,
                 prog_type,
+                gitignore: None,
                 name,
                 path
             })
@@ -212,6 +538,7 @@ mod tests {
             temp,
             path,
             project,
+            ..NewArgs::default()
         }
     }
 
@@ -233,6 +560,183 @@ mod tests {
         assert_eq!(args.command, Commands::New(new_args));
     }
 
+    #[test]
+    fn test_new_link() {
+        let args = Cli::parse_from(["prog", "new", "--link", "--strip-ext", "test.py"]);
+        let Commands::New(new) = args.command else { panic!() };
+        assert!(new.link && new.strip_ext);
+
+        // Only scripts that are kept can be linked
+        assert!(Cli::try_parse_from(["prog", "new", "-D", "--link", "test"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "new", "-t", "--link"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "new", "--link", "--no-link", "test.py"]).is_err());
+    }
+
+    #[test]
+    fn test_new_template() {
+        let args = Cli::parse_from([
+            "prog", "new", "-D", "-G", "Fun,Util", "-T", "Python", "--template", "service", "test",
+        ]);
+        let mut new_args = create_new_args();
+        new_args.template = Some(String::from("service"));
+        assert_eq!(args.command, Commands::New(new_args));
+
+        // Templates can only be used with projects
+        assert!(Cli::try_parse_from(["prog", "new", "--template", "service", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_new_license() {
+        let args = Cli::parse_from([
+            "prog", "new", "-D", "-G", "Fun,Util", "-T", "Python", "--license", "apache-2.0", "test",
+        ]);
+        let mut new_args = create_new_args();
+        new_args.license = Some(String::from("Apache-2.0"));
+        assert_eq!(args.command, Commands::New(new_args));
+
+        assert!(Cli::try_parse_from(["prog", "new", "-D", "--license", "WTFPL", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_gitignore() {
+        let args = Cli::parse_from([
+            "prog", "new", "-D", "-G", "Fun,Util", "-T", "Python", "--gitignore=py,VSCode,linux", "test",
+        ]);
+        let mut new_args = create_new_args();
+        new_args.gitignore = Some(vec![String::from("python"), String::from("vscode"), String::from("linux")]);
+        assert_eq!(args.command, Commands::New(new_args));
+        assert!(Cli::try_parse_from(["prog", "new", "-D", "--gitignore=cobol", "test"]).is_err());
+
+        // The snippets are optional when adding, leaving the positional args alone
+        let args = Cli::parse_from(["prog", "add", "--gitignore", "test", "/tmp/test"]);
+        let Commands::Add(add) = args.command else { panic!() };
+        assert_eq!(Some(vec![]), add.gitignore);
+        assert_eq!("test", add.name);
+
+        let args = Cli::parse_from(["prog", "gitignore", "-e", "test", "rust", "vim"]);
+        assert_eq!(
+            args.command,
+            Commands::Gitignore(GitignoreArgs {
+                list: false,
+                entry: Some(String::from("test")),
+                snippets: vec![String::from("rust"), String::from("vim")]
+            })
+        );
+        assert!(Cli::try_parse_from(["prog", "gitignore"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "gitignore", "--list", "rust"]).is_err());
+    }
+
+    #[test]
+    fn test_new_set() {
+        let args = Cli::parse_from([
+            "prog", "new", "-D", "-G", "Fun,Util", "-T", "Python", "--set", "docker=yes", "--set", "cmd=a=b", "test",
+        ]);
+        let mut new_args = create_new_args();
+        new_args.set = vec![
+            (String::from("docker"), String::from("yes")),
+            (String::from("cmd"), String::from("a=b")),
+        ];
+        assert_eq!(args.command, Commands::New(new_args));
+
+        assert!(Cli::try_parse_from(["prog", "new", "--set", "docker", "test"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "new", "--set", "=yes", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_open() {
+        let args = Cli::parse_from(["prog", "open", "test"]);
+        assert_eq!(args.command, Commands::Open(OpenArgs { name: String::from("test") }));
+        assert!(!args.command.modifies_config());
+
+        let args = Cli::parse_from(["prog", "new", "-D", "-G", "Fun,Util", "-T", "Python", "--open", "test"]);
+        let mut new_args = create_new_args();
+        new_args.open = true;
+        assert_eq!(args.command, Commands::New(new_args));
+
+        assert!(Cli::try_parse_from(["prog", "new", "--open", "--dry-run", "test"]).is_err());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let args = Cli::parse_from(["prog", "migrate", "--check"]);
+        assert_eq!(args.command, Commands::Migrate(MigrateArgs { check: true }));
+    }
+
+    #[test]
+    fn test_restore() {
+        let args = Cli::parse_from(["prog", "restore", "2"]);
+        assert_eq!(args.command, Commands::Restore(RestoreArgs { list: false, backup: Some(2) }));
+
+        assert!(Cli::try_parse_from(["prog", "restore"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "restore", "--list", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        let args = Cli::parse_from(["prog", "config", "convert", "--to", "JSON"]);
+        assert_eq!(
+            args.command,
+            Commands::Config(ConfigArgs { command: ConfigCommands::Convert { to: Format::Json, output: None } })
+        );
+
+        let args = Cli::parse_from(["prog", "config", "convert", "--to=yml", "-o", "catalog.yaml"]);
+        assert_eq!(
+            args.command,
+            Commands::Config(ConfigArgs {
+                command: ConfigCommands::Convert { to: Format::Yaml, output: Some(PathBuf::from("catalog.yaml")) }
+            })
+        );
+
+        assert!(Cli::try_parse_from(["prog", "config", "convert", "--to", "xml"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "config", "convert"]).is_err());
+    }
+
+    #[test]
+    fn test_profile() {
+        let args = Cli::parse_from(["prog", "--profile", "work", "list"]);
+        assert_eq!(Some(String::from("work")), args.profile);
+
+        let args = Cli::parse_from(["prog", "remove", "old", "--profile=work"]);
+        assert_eq!(Some(String::from("work")), args.profile);
+
+        let args = Cli::parse_from(["prog", "profile", "create", "--use", "-p", "/tmp/work", "work"]);
+        assert_eq!(None, args.profile);
+        assert_eq!(
+            args.command,
+            Commands::Profile(ProfileArgs {
+                command: ProfileCommands::Create { path: Some(PathBuf::from("/tmp/work")), switch: true, name: String::from("work") }
+            })
+        );
+
+        let args = Cli::parse_from(["prog", "profile", "use", "default"]);
+        assert_eq!(args.command, Commands::Profile(ProfileArgs { command: ProfileCommands::Use { name: String::from("default") } }));
+
+        assert!(Cli::try_parse_from(["prog", "profile", "remove"]).is_err());
+    }
+
+    #[test]
+    fn test_template() {
+        let args = Cli::parse_from(["prog", "template", "add", "service", "./service"]);
+        assert_eq!(
+            args.command,
+            Commands::Template(TemplateArgs {
+                command: TemplateCommands::Add { name: String::from("service"), dir: PathBuf::from("./service") }
+            })
+        );
+
+        let args = Cli::parse_from(["prog", "template", "remove", "-f", "service"]);
+        assert_eq!(
+            args.command,
+            Commands::Template(TemplateArgs {
+                command: TemplateCommands::Remove { force: true, name: String::from("service") }
+            })
+        );
+        assert!(!args.command.modifies_config());
+
+        assert!(Cli::try_parse_from(["prog", "template", "show"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "template", "export", "service"]).is_err());
+    }
+
     #[test]
     #[should_panic]
     fn test_new_panic() {
@@ -266,12 +770,36 @@ mod tests {
                     description,
                     tags,
                     rm_tags,
-                    prog_type
+                    prog_type,
+                    license: None
                 }
             })
         )
     }
 
+    #[test]
+    fn test_retype() {
+        let args = Cli::parse_from(["prog", "retype", "--detect"]);
+        assert_eq!(
+            args.command,
+            Commands::Retype(RetypeArgs { detect: true, prog_type: None, names: vec![] })
+        );
+
+        let args = Cli::parse_from(["prog", "retype", "-T", "go", "one", "two"]);
+        assert_eq!(
+            args.command,
+            Commands::Retype(RetypeArgs {
+                detect: false,
+                prog_type: Some("go".to_string()),
+                names: vec!["one".to_string(), "two".to_string()]
+            })
+        );
+
+        // Either --detect or --type is needed, and --type needs names
+        assert!(Cli::try_parse_from(["prog", "retype", "one"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "retype", "-T", "go"]).is_err());
+    }
+
     #[test]
     #[should_panic]
     fn test_edit_panic() {
@@ -0,0 +1,127 @@
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::BoilResult;
+
+/// Number of previous configs kept when `backups` isn't set
+pub const DEFAULT_BACKUPS: usize = 10;
+
+/// A previous version of the config
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Milliseconds since the unix epoch when it was replaced
+    pub millis: u128,
+}
+
+/// Replaces the file at `path` with `contents` by writing a temporary file next
+/// to it and renaming it over `path`, so `path` is never left half written.
+/// A symlink at `path` is followed, and the file keeps its permissions
+pub fn atomic_write(path: &Path, contents: &[u8]) -> BoilResult<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let permissions = fs::metadata(&path).ok().map(|m| m.permissions());
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.tmp", name));
+
+    let res = File::create(&tmp).and_then(|mut f| {
+        if let Some(p) = permissions {
+            f.set_permissions(p)?;
+        }
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+
+    if let Err(e) = res.and_then(|_| fs::rename(&tmp, &path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into())
+    }
+
+    Ok(())
+}
+
+/// `<file>.<suffix>` next to the file at `path`, numbered if that is taken so no
+/// earlier copy is ever overwritten
+pub fn unused_name(path: &Path, suffix: &str) -> BoilResult<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut unused = path.with_file_name(format!("{}.{}", name, suffix));
+    let mut n = 1;
+
+    while unused.try_exists()? {
+        unused = path.with_file_name(format!("{}.{}.{}", name, suffix, n));
+        n += 1;
+    }
+
+    Ok(unused)
+}
+
+/// Directory the backups of the config at `path` are kept in
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.with_file_name("backups")
+}
+
+/// Backups of the config at `path`, newest first
+pub fn list(path: &Path) -> BoilResult<Vec<Backup>> {
+    let dir = backup_dir(path);
+
+    if !dir.is_dir() {
+        return Ok(vec![])
+    }
+
+    let prefix = format!("{}.", path.file_name().unwrap_or_default().to_string_lossy());
+    let mut backups = vec![];
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if let Some(Ok(millis)) = name.strip_prefix(&prefix).map(|m| m.parse::<u128>()) {
+            backups.push(Backup { path: entry.path(), millis });
+        }
+    }
+
+    backups.sort_by_key(|b| Reverse(b.millis));
+    Ok(backups)
+}
+
+/// Saves the current config at `path` as the newest backup, unless it is the same
+/// as `next` (the config about to be written), then removes all but the newest `keep`
+pub fn rotate(path: &Path, next: &[u8], keep: usize) -> BoilResult<()> {
+    if keep == 0 || !path.is_file() {
+        return Ok(())
+    }
+
+    let current = fs::read(path)?;
+
+    if current.is_empty() || current == next {
+        return Ok(())
+    }
+
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+
+    // Keep backups made within the same millisecond apart
+    while let Some(last) = list(path)?.first() {
+        if last.millis < millis {
+            break
+        }
+        millis = last.millis + 1;
+    }
+
+    atomic_write(&dir.join(format!("{}.{}", name, millis)), &current)?;
+
+    for old in list(path)?.iter().skip(keep) {
+        fs::remove_file(&old.path)?;
+    }
+
+    Ok(())
+}
@@ -1,17 +1,26 @@
 use std::fs;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::collections::hash_map::Iter;
 
 use serde::{Deserialize, Serialize};
+use toml::Value;
 use prettytable::{Table, Row, Cell, row};
 
 use crate::error::{BoilError, BoilResult};
-use crate::defaults::{default_bin_path, default_proj_path};
+use crate::defaults::{default_backups, default_bin_path, default_cache_path, default_link_path, default_proj_path, default_template_path};
 use crate::args::ListOpts;
+use crate::backup;
+use crate::migrate::{self, Report, CONFIG_VERSION};
+use crate::project::{Language, Languages};
+use crate::storage::Format;
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
+    /// Schema version, used to upgrade configs written by older versions of boil
+    #[serde(default = "config_version")]
+    pub version: u32,
+
     #[serde(default)]
     pub programs: Programs,
 
@@ -19,13 +28,44 @@ pub struct Config {
     pub defaults: DefCfg,
 
     #[serde(default)]
-    pub temp: Program
+    pub temp: Program,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty", serialize_with = "sorted")]
+    pub languages: HashMap<String, Language>
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DefCfg {
     pub proj_path: PathBuf,
-    pub bin_path: PathBuf
+    pub bin_path: PathBuf,
+    #[serde(default = "default_template_path")]
+    pub template_path: PathBuf,
+    /// Where templates cloned from git repositories are kept
+    #[serde(default = "default_cache_path")]
+    pub cache_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Initialize a git repository in every new project
+    #[serde(default)]
+    pub git: bool,
+    /// Commit the generated files when initializing a repository
+    #[serde(default)]
+    pub git_commit: bool,
+    /// Remove the newly created files when a hook fails instead of only reporting it
+    #[serde(default)]
+    pub hook_rollback: bool,
+    /// Link every new script into `link_path`
+    #[serde(default)]
+    pub link: bool,
+    /// Directory on PATH new scripts are linked into
+    #[serde(default = "default_link_path")]
+    pub link_path: PathBuf,
+    /// Leave the extension off of linked scripts, ie `my_script` instead of `my_script.sh`
+    #[serde(default)]
+    pub link_strip_ext: bool,
+    /// Number of previous versions of the config kept for `boil restore`, 0 to keep none
+    #[serde(default = "default_backups")]
+    pub backups: usize
 }
 
 /// Object representing the last file/project added
@@ -38,7 +78,7 @@ pub struct Temp {
 pub type ProgMap = HashMap<String, Program>;
 
 #[derive(Serialize, Deserialize, Default, Debug)]
-pub struct Programs(pub ProgMap);
+pub struct Programs(#[serde(serialize_with = "sorted")] pub ProgMap);
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Program {
@@ -48,17 +88,19 @@ pub struct Program {
     #[serde(rename = "type")]
     pub prog_type: ProgType,
     pub description: Option<String>,
-    pub tags: Option<Vec<String>>
+    pub tags: Option<Vec<String>>,
+    /// SPDX identifier of the entry's license
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Symlink to the script created with `--link`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PathBuf>
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
-pub enum ProgType {
-    Python,
-    Rust,
-    JavaScript,
-    #[default]
-    Bash
-}
+/// Id of the entry's language in the language registry, ie `python`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct ProgType(String);
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Field {
@@ -67,16 +109,48 @@ pub enum Field {
     Path(PathBuf),
     Type(ProgType),
     Description(Option<String>),
-    Tags(Option<Vec<String>>)
+    Tags(Option<Vec<String>>),
+    License(Option<String>)
+}
+
+
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            programs: Programs::default(),
+            defaults: DefCfg::default(),
+            temp: Program::default(),
+            languages: HashMap::new()
+        }
+    }
 }
 
+/// Writes maps in key order so writing an unchanged config gives the same file
+fn sorted<S: serde::Serializer, V: Serialize>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<&String, &V>>().serialize(serializer)
+}
 
+fn config_version() -> u32 {
+    CONFIG_VERSION
+}
 
 impl Default for DefCfg {
     fn default() -> Self {
         Self {
             proj_path: default_proj_path(),
-            bin_path: default_bin_path()
+            bin_path: default_bin_path(),
+            template_path: default_template_path(),
+            cache_path: default_cache_path(),
+            author: None,
+            git: false,
+            git_commit: false,
+            hook_rollback: false,
+            link: false,
+            link_path: default_link_path(),
+            link_strip_ext: false,
+            backups: default_backups()
         }
     }
 }
@@ -89,7 +163,7 @@ impl Program {
             ListOpts::Name => self.name.as_bytes().into(),
             ListOpts::Path => self.path.to_str().unwrap().as_bytes().to_vec(),
             ListOpts::Project => vec![self.project.into()],
-            ListOpts::Type => format!("{:?}", self.prog_type).as_bytes().to_vec(),
+            ListOpts::Type => self.prog_type.as_str().as_bytes().to_vec(),
             ListOpts::Description => self
                 .description
                 .clone()
@@ -103,6 +177,12 @@ impl Program {
                 .iter()
                 .flat_map(|f| f.as_bytes().to_owned())
                 .collect(),
+            ListOpts::License => self
+                .license
+                .clone()
+                .unwrap_or_default()
+                .as_bytes()
+                .to_vec(),
         }
     }
 }
@@ -111,48 +191,92 @@ impl ProgType {
     pub fn new() -> Self {
         Self::default()
     }
-    
-    pub fn from_string(prog_type: &str) -> Self {
-        match prog_type.to_lowercase().as_str() {
-            "py" | "python" => ProgType::Python,
-            "js" | "javascript" => ProgType::JavaScript,
-            "rs" | "rust" => ProgType::Rust,
-            _ => ProgType::Bash
-        }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
-    
-    pub fn ext(&self) -> String {
-        match self {
-            Self::Python => "py".to_string(),
-            Self::JavaScript => "js".to_string(),
-            Self::Rust => "rs".to_string(),
-            _ => "sh".to_string()
-        }
+}
+
+impl Default for ProgType {
+    fn default() -> Self {
+        Self::from("bash")
+    }
+}
+
+impl From<&str> for ProgType {
+    fn from(id: &str) -> Self {
+        Self(id.to_owned())
+    }
+}
+
+impl std::fmt::Display for ProgType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
 impl Config {
+    /// Loads the config at `path`. Configs written by older versions of boil are
+    /// upgraded and saved, keeping a backup of the original
     pub fn from(path: &PathBuf) -> BoilResult<Self> {
-        let config: Config;
+        let (config, report) = Self::read(path)?;
+
+        // A freshly initialized config is still empty, nothing to back up
+        let content = fs::read_to_string(path)?;
+        if report.is_needed() && !content.trim().is_empty() {
+            let backup = migrate::backup(path, &content, report.from)?;
+            config.write(path)?;
+            eprintln!(
+                "boil: upgraded config from version {} to {} - the original was saved to {}",
+                report.from, report.to, backup.to_string_lossy()
+            );
+        }
 
-        if path.try_exists().unwrap_or(false) {
-            let content = fs::read_to_string(path)?;
-            config = toml::from_str(&content)?;
-        } else {
+        Ok(config)
+    }
+
+    /// Reports how loading the config at `path` would upgrade it, without changing it
+    pub fn check(path: &PathBuf) -> BoilResult<Report> {
+        Self::read(path).map(|(_, report)| report)
+    }
+
+    /// Loads the config at `path` along with how it was upgraded, without saving the upgrade
+    pub fn read(path: &PathBuf) -> BoilResult<(Self, Report)> {
+        if !path.try_exists().unwrap_or(false) {
             return Err(BoilError::NeedInit)
         }
 
-        Ok(config)
+        Self::parse(&fs::read_to_string(path)?, Format::of(path)?)
+    }
+
+    /// Reads a config in `format` from `content`, upgrading it to the current version
+    pub fn parse(content: &str, format: Format) -> BoilResult<(Self, Report)> {
+        // A freshly initialized config is empty whatever its format
+        let mut table = match content.trim() {
+            "" => toml::Table::new(),
+            _ => format.storage().load(content)?,
+        };
+        let report = migrate::migrate(&mut table)?;
+        let config: Config = Value::Table(table).try_into()?;
+
+        Ok((config, report))
+    }
+
+    /// The language registry - built-in languages merged with `[languages]`
+    pub fn languages(&self) -> Languages {
+        Languages::new(&self.languages)
     }
 
     pub fn get(&self, entry: String) -> Option<&Program> {
         self.programs.0.get(&entry)
     }
 
-    pub fn write(&self, path: &PathBuf) -> BoilResult<()> {
-        let config_str = toml::to_string_pretty(&self)?;
+    /// Replaces the config at `path`, saving the previous version to its backups first
+    pub fn write(&self, path: &Path) -> BoilResult<()> {
+        let config_str = Format::of(path)?.storage().dump(self)?;
 
-        fs::write(path, config_str)?;
+        backup::rotate(path, config_str.as_bytes(), self.defaults.backups)?;
+        backup::atomic_write(path, config_str.as_bytes())?;
 
         Ok(())
     }
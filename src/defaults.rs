@@ -1,23 +1,41 @@
 use std::env;
 use std::fs;
+use std::process::Command;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
+use crate::backup::DEFAULT_BACKUPS;
+use crate::lock::DEFAULT_LOCK_TIMEOUT;
 use crate::error::{BoilResult, BoilError};
 
 pub fn default_config() -> BoilResult<PathBuf> {
     if let Ok(p) = env::var("BOIL_DEF_CONFIG") {
         let path: PathBuf = PathBuf::from(&p);
 
-        Ok(path)
+        Ok(find_config(path))
     } else if let Some(home) = dirs::config_dir() {
         let path: PathBuf = [home.as_path(), Path::new(".boil/config.toml")].iter().collect();
 
-        Ok(path)
+        Ok(find_config(path))
     } else {
         Err(BoilError::ConfigCreate)
     }
 }
 
+/// The config at `path` in whichever format it exists in, so a config converted to
+/// another format is picked up by its extension. `path` itself if it exists or there is none yet
+pub fn find_config(path: PathBuf) -> PathBuf {
+    if path.is_file() {
+        return path
+    }
+
+    ["toml", "json", "yaml", "yml"]
+        .iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|p| p.is_file())
+        .unwrap_or(path)
+}
+
 pub fn default_proj_path() -> PathBuf {
     if let Ok(p) = env::var("BOIL_PROJ_PATH") {
         let path: PathBuf = PathBuf::from(&p);
@@ -40,4 +58,64 @@ pub fn default_bin_path() -> PathBuf {
     } else {
         panic!()
     }
+}
+
+pub fn default_link_path() -> PathBuf {
+    if let Some(home) = dirs::home_dir() {
+        PathBuf::from_iter([home.as_path(), Path::new(".local/bin")])
+    } else {
+        panic!()
+    }
+}
+
+pub fn default_backups() -> usize {
+    DEFAULT_BACKUPS
+}
+
+/// How long to wait for another boil command to release the config, `BOIL_LOCK_TIMEOUT`
+/// seconds if set
+pub fn default_lock_timeout() -> Duration {
+    let secs = env::var("BOIL_LOCK_TIMEOUT")
+        .ok()
+        .and_then(|s| f64::from_str(&s).ok())
+        .filter(|s| s.is_finite() && *s >= 0.0)
+        .unwrap_or(DEFAULT_LOCK_TIMEOUT as f64);
+
+    Duration::from_secs_f64(secs)
+}
+
+pub fn default_template_path() -> PathBuf {
+    if let Ok(p) = env::var("BOIL_TEMPLATE_PATH") {
+        PathBuf::from(&p)
+    } else if let Some(home) = dirs::config_dir() {
+        PathBuf::from_iter([home.as_path(), Path::new(".boil/templates")])
+    } else {
+        panic!()
+    }
+}
+
+pub fn default_cache_path() -> PathBuf {
+    if let Ok(p) = env::var("BOIL_CACHE_PATH") {
+        PathBuf::from(&p)
+    } else if let Some(home) = dirs::config_dir() {
+        PathBuf::from_iter([home.as_path(), Path::new(".boil/cache")])
+    } else {
+        panic!()
+    }
+}
+
+/// Name used for `{{author}}` when none is set in the config - taken
+/// from git's `user.name`, falling back to the current user
+pub fn default_author() -> String {
+    let git_name = Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
+        .filter(|n| !n.is_empty());
+
+    git_name
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
 }
\ No newline at end of file
@@ -11,16 +11,26 @@ pub enum BoilError {
     DeToml(#[from] toml::de::Error),
     #[error("Unable to serialize TOML")]
     SeToml(#[from] toml::ser::Error),
+    #[error("Unable to read or write JSON - {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unable to read or write YAML - {0}")]
+    Yaml(#[from] serde_norway::Error),
+    #[error("Unknown config format '{0}' - available formats are toml, json and yaml")]
+    UnknownFormat(String),
     #[error("Unable to create config file")]
     ConfigCreate,
     #[error("Path already exists - {0}")]
     PathExists(PathBuf),
     #[error("{0} - Path is not valid to add as a program")]
     InvalidPath(PathBuf),
+    #[error("'{0}' can't be used as the name of a new project - {1}")]
+    InvalidName(String, String),
     #[error("Program with name '{0}' already exists")]
     NameExists(String),
     #[error("No entry found for '{0}' in config")]
     NotFound(String),
+    #[error("'{0}' is not a project")]
+    NotAProject(String),
     #[error("Unknown format option - '{0}'")]
     ListFormat(String),
     #[error("Unknown sort option - '{0}'")]
@@ -29,6 +39,48 @@ pub enum BoilError {
     ConfigExists(String),
     #[error("Config file not found - please use 'boil init' to create")]
     NeedInit,
+    #[error("Config version {0} is newer than this version of boil supports ({1}) - please upgrade boil")]
+    ConfigVersion(u32, u32),
+    #[error("Config at {0} is in use by another boil command - gave up waiting after {1}s")]
+    Locked(PathBuf, f32),
+    #[error("Config at {0} was changed by another program since it was loaded - not overwriting it, please run the command again")]
+    ConfigModified(PathBuf),
+    #[error("No backup number {0} - see 'boil restore --list'")]
+    BackupNotFound(usize),
+    #[error("No template named '{0}' found")]
+    TemplateNotFound(String),
+    #[error("'{0}' is not a valid template name")]
+    InvalidTemplate(String),
+    #[error("Template '{0}' isn't cached at that version - it can't be previewed without fetching it")]
+    TemplateNotCached(String),
+    #[error("Template '{0}' extends itself")]
+    TemplateCycle(String),
+    #[error("Invalid answer for '{0}' - {1}")]
+    InvalidAnswer(String, String),
+    #[error("No profile named '{0}' - see 'boil profile list', or create it with 'boil profile create {0}'")]
+    ProfileNotFound(String),
+    #[error("Profile '{0}' already exists")]
+    ProfileExists(String),
+    #[error("'{0}' is not a valid profile name")]
+    InvalidProfile(String),
+    #[error("Unknown program type '{0}' - add it under [languages] in the config to use it")]
+    UnknownType(String),
+    #[error("Unable to run git - is it installed and on your PATH?")]
+    GitMissing,
+    #[error("git {0} failed - {1}")]
+    Git(String, String),
+    #[error("Unknown license '{0}' - available licenses are {1}")]
+    UnknownLicense(String, String),
+    #[error("Unknown .gitignore snippet '{0}' - available snippets are {1}")]
+    UnknownGitignore(String, String),
+    #[error("No editor found - set $VISUAL or $EDITOR, or `editor` for the entry's language in the config")]
+    EditorMissing,
+    #[error("Unable to run editor '{0}' - {1}")]
+    Editor(String, String),
+    #[error("Hook '{0}' failed with exit code {1}")]
+    HookFailed(String, String),
+    #[error("Hook '{0}' was killed by signal {1}")]
+    HookKilled(String, String),
 }
 
 pub type BoilResult<T> = Result<T, BoilError>;
\ No newline at end of file
@@ -1,13 +1,22 @@
 #![allow(unused)]
 #![allow(dead_code)]
 pub mod args;
+mod backup;
 mod defaults;
 mod config;
 pub mod error;
+mod lock;
+mod migrate;
+mod profile;
 mod project;
+mod storage;
 pub mod table;
+mod template;
 pub mod utils;
 
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::env::temp_dir;
 use std::fs::{self, metadata};
 use std::path::{Path, PathBuf};
@@ -16,12 +25,15 @@ use std::io::{self, Write};
 use serde::{Deserialize, Serialize};
 
 use config::{Config, Program, ProgMap, Temp, ProgType};
-use args::{AddArgs, Commands, EditArgs, InitArgs, ListArgs, NewArgs, RemoveArgs};
+use args::{AddArgs, Commands, ConfigArgs, ConfigCommands, EditArgs, GitignoreArgs, InitArgs, ListArgs, MigrateArgs, NewArgs, OpenArgs, ProfileArgs, ProfileCommands, RemoveArgs, RestoreArgs, RetypeArgs, TemplateArgs, TemplateCommands};
 use error::{BoilResult, BoilError};
-use defaults::default_config;
-use project::{create_program, create_project};
+use defaults::{default_author, default_config, default_lock_timeout};
+use project::{extend_gitignore, git, gitignore, link_path, link_program, open, plan_gitignore, plan_license, plan_program, plan_project, render_hook, run_hooks, Plan, PlannedFile, Rollback, Vars};
+use lock::ConfigLock;
+use storage::Format;
 use table::BoilTable;
-use utils::user_input;
+use template::{answer, Template};
+use utils::{user_input, utc_datetime};
 use colorize::{print_color, colorize};
 
 
@@ -29,6 +41,14 @@ use colorize::{print_color, colorize};
 pub struct Boil {
     pub config: Config,
     pub cfg_path: PathBuf,
+    /// Held until the boil is dropped, so no other boil command changes the config meanwhile
+    lock: Option<ConfigLock>,
+    /// Digest of the config file as it was loaded or last written
+    loaded: Option<u64>,
+    /// Whether the config was handed back early, see `release`
+    released: bool,
+    /// Whether questions, ie template prompts, can be asked on the terminal
+    pub interactive: bool,
 }
 
 impl Boil {
@@ -36,15 +56,55 @@ impl Boil {
         Boil::default()
     }
 
+    /// Loads the config to change it, keeping other boil commands out until dropped
     pub fn from(p: Option<PathBuf>) -> BoilResult<Self> {
+        Self::load(p, false)
+    }
+
+    /// Loads the config only to read it, which other commands reading it can do meanwhile.
+    /// The config must not be written
+    pub fn from_shared(p: Option<PathBuf>) -> BoilResult<Self> {
+        Self::load(p, true)
+    }
+
+    fn load(p: Option<PathBuf>, shared: bool) -> BoilResult<Self> {
         let cfg_path = match p {
             Some(pb) => pb,
             None => default_config()?,
         };
 
-        let config = Config::from(&cfg_path)?;
+        if !cfg_path.try_exists().unwrap_or(false) {
+            return Err(BoilError::NeedInit)
+        }
+
+        let (lock, config) = match shared {
+            true => Self::read_shared(&cfg_path)?,
+            false => (ConfigLock::acquire(&cfg_path, default_lock_timeout())?, Config::from(&cfg_path)?),
+        };
+        let loaded = digest(&cfg_path)?;
+
+        Ok(Self { config, cfg_path, lock: Some(lock), loaded: Some(loaded), ..Self::default() })
+    }
+
+    /// Loads the config at `path` under a shared lock. Upgrading an old config saves it,
+    /// so that is done holding the lock alone before sharing it again
+    fn read_shared(path: &PathBuf) -> BoilResult<(ConfigLock, Config)> {
+        let timeout = default_lock_timeout();
+        let lock = ConfigLock::acquire_shared(path, timeout)?;
+        let (config, report) = Config::read(path)?;
+
+        if !report.is_needed() {
+            return Ok((lock, config))
+        }
 
-        Ok(Self { config, cfg_path })
+        drop(lock);
+        let exclusive = ConfigLock::acquire(path, timeout)?;
+        Config::from(path)?;
+        drop(exclusive);
+
+        let lock = ConfigLock::acquire_shared(path, timeout)?;
+        let (config, _) = Config::read(path)?;
+        Ok((lock, config))
     }
 
     pub fn run(&mut self, cmd: Commands) -> BoilResult<()> {
@@ -54,6 +114,10 @@ impl Boil {
             Commands::Edit(c) => self.edit(c)?,
             Commands::List(c) => self.list(c)?,
             Commands::Remove(c) => self.remove(c)?,
+            Commands::Open(c) => self.open(c)?,
+            Commands::Retype(c) => self.retype(c)?,
+            Commands::Gitignore(c) => self.gitignore(c)?,
+            Commands::Template(c) => self.template(c)?,
             _ => {}
         };
 
@@ -104,6 +168,214 @@ impl Boil {
         Ok(())
     }
 
+    /// Upgrades the config at `path` to the current version, or with `--check` only
+    /// reports what would change
+    pub fn migrate(args: MigrateArgs, path: Option<PathBuf>) -> BoilResult<()> {
+        let cfg_path = match path {
+            Some(p) => p,
+            None => default_config()?
+        };
+
+        let report = Config::check(&cfg_path)?;
+
+        if !report.is_needed() {
+            print_color!(Fgb->"Config is up to date at version", b->report.to);
+            return Ok(())
+        }
+
+        if args.check {
+            print_color!(Fy->"Config would be upgraded from version", b->report.from, Fy->"to", b->report.to);
+        } else {
+            let _lock = ConfigLock::acquire(&cfg_path, default_lock_timeout())?;
+            Config::from(&cfg_path)?;
+            print_color!(Fgb->"Upgraded config from version", b->report.from, Fgb->"to", b->report.to);
+        }
+
+        for change in report.changes.iter() {
+            println!("  {}", change);
+        }
+
+        Ok(())
+    }
+
+    /// Lists the backups of the config at `path`, or replaces the config with one of them.
+    /// The config being replaced is backed up as well, so a restore can be undone
+    pub fn restore(args: RestoreArgs, path: Option<PathBuf>) -> BoilResult<()> {
+        let cfg_path = match path {
+            Some(p) => p,
+            None => default_config()?
+        };
+
+        let backups = backup::list(&cfg_path)?;
+        let format = Format::of(&cfg_path)?;
+
+        let Some(n) = args.backup else {
+            if backups.is_empty() {
+                print_color!(Fy->"No backups found in", b->backup::backup_dir(&cfg_path).to_string_lossy());
+            }
+
+            for (i, b) in backups.iter().enumerate() {
+                let entries = match fs::read_to_string(&b.path).map(|c| Config::parse(&c, format)) {
+                    Ok(Ok((config, _))) => format!("{} entries", config.len()),
+                    _ => String::from("unreadable"),
+                };
+                println!("{:>3}  {}  {}", i + 1, utc_datetime((b.millis / 1000) as u64), entries);
+            }
+
+            return Ok(())
+        };
+
+        let _lock = ConfigLock::acquire(&cfg_path, default_lock_timeout())?;
+        let b = n.checked_sub(1)
+            .and_then(|i| backups.get(i))
+            .ok_or(BoilError::BackupNotFound(n))?;
+        // The backup is put back byte for byte, it only has to be a readable config
+        let contents = fs::read(&b.path)?;
+        let (config, _) = Config::parse(&String::from_utf8_lossy(&contents), format)?;
+
+        backup::rotate(&cfg_path, &contents, config.defaults.backups)?;
+        backup::atomic_write(&cfg_path, &contents)?;
+        print_color!(Fgb->"Restored config from", b->utc_datetime((b.millis / 1000) as u64));
+
+        Ok(())
+    }
+
+    /// Path of the config of `profile`, or the active profile if not given. Profiles
+    /// are kept next to `base`, the config of the default profile
+    pub fn profile_config(base: Option<PathBuf>, profile: Option<&str>) -> BoilResult<PathBuf> {
+        let base = match base {
+            Some(p) => p,
+            None => default_config()?
+        };
+
+        profile::find(&base, profile)
+    }
+
+    pub fn profile(args: ProfileArgs, base: Option<PathBuf>) -> BoilResult<()> {
+        let base = match base {
+            Some(p) => p,
+            None => default_config()?
+        };
+
+        match args.command {
+            ProfileCommands::List => {
+                let active = profile::active(&base)?;
+
+                for name in profile::list(&base)? {
+                    let path = profile::config_path(&base, &name);
+                    let content = fs::read_to_string(&path);
+                    let entries = match content.map(|c| Config::parse(&c, Format::of(&path)?)) {
+                        Ok(Ok((config, _))) => format!("{} entries", config.len()),
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => String::from("not initialized"),
+                        _ => String::from("unreadable"),
+                    };
+                    let marker = if name == active { "*" } else { " " };
+                    println!("{} {:<16} {:<16} {}", marker, name, entries, path.to_string_lossy());
+                }
+            },
+            ProfileCommands::Create { path, switch, name } => {
+                let cfg_path = profile::create(&base, &name)?;
+                let mut config = Config::default();
+
+                if let Some(p) = path {
+                    config.set_proj_path(&p);
+                }
+
+                config.write(&cfg_path)?;
+                print_color!(Fgb->"Created profile", b->&name, Fgb->"at", b->cfg_path.to_string_lossy());
+
+                if switch {
+                    profile::remember(&base, &name)?;
+                    print_color!(Fgb->"Now using profile", b->&name);
+                }
+            },
+            ProfileCommands::Use { name } => {
+                if !profile::exists(&base, &name) {
+                    return Err(BoilError::ProfileNotFound(name))
+                }
+
+                profile::remember(&base, &name)?;
+                print_color!(Fgb->"Now using profile", b->&name);
+            },
+            ProfileCommands::Remove { force, name } => {
+                if name == profile::DEFAULT_PROFILE {
+                    return Err(BoilError::InvalidProfile(name))
+                }
+                if !profile::exists(&base, &name) {
+                    return Err(BoilError::ProfileNotFound(name))
+                }
+
+                if !force {
+                    let msg = colorize!(b->"Remove profile ", bFg->&name, b->" and all of its entries - [y/N]");
+                    if !user_input(msg)? {
+                        return Ok(())
+                    }
+                }
+
+                profile::remove(&base, &name)?;
+                print_color!(Fgb->"Removed profile", b->&name);
+            },
+        }
+
+        Ok(())
+    }
+
+    pub fn config(args: ConfigArgs, path: Option<PathBuf>) -> BoilResult<()> {
+        let cfg_path = match path {
+            Some(p) => p,
+            None => default_config()?
+        };
+
+        match args.command {
+            ConfigCommands::Convert { to, output } => Self::convert(&cfg_path, to, output),
+        }
+    }
+
+    /// Writes the config at `path` in the format `to`, either to `output` or in place of the
+    /// config. The config then takes the new format's extension and the original is kept as `<file>.bak`
+    fn convert(path: &Path, to: Format, output: Option<PathBuf>) -> BoilResult<()> {
+        if !path.try_exists().unwrap_or(false) {
+            return Err(BoilError::NeedInit)
+        }
+
+        let _lock = ConfigLock::acquire(path, default_lock_timeout())?;
+        let config = Config::from(&path.to_path_buf())?;
+        let content = to.storage().dump(&config)?;
+
+        if let Some(out) = output {
+            if out.try_exists()? {
+                return Err(BoilError::PathExists(out))
+            }
+            fs::write(&out, content)?;
+            print_color!(Fgb->"Wrote config as", b->to, Fgb->"to", b->out.to_string_lossy());
+            return Ok(())
+        }
+
+        let dest = path.with_extension(to.extension());
+
+        if dest == path {
+            if Format::of(path)? == to {
+                print_color!(Fgb->"Config is already", b->to);
+                return Ok(())
+            }
+        } else if dest.try_exists()? {
+            return Err(BoilError::PathExists(dest))
+        }
+
+        let kept = backup::unused_name(path, "bak")?;
+        fs::copy(path, &kept)?;
+        backup::atomic_write(&dest, content.as_bytes())?;
+
+        if dest != path {
+            fs::remove_file(path)?;
+        }
+
+        print_color!(Fgb->"Converted config to", b->to, Fgb->"at", b->dest.to_string_lossy());
+        println!("  the original was kept at {}", kept.to_string_lossy());
+
+        Ok(())
+    }
+
     fn add_existing(&mut self, args: AddArgs) -> BoilResult<()> {
         let (description, tags, name, path) = 
             (args.description, args.tags, args.name, args.path);
@@ -118,12 +390,41 @@ impl Boil {
             _ => return Err(BoilError::InvalidPath(path.to_owned()))
         };
 
+        let languages = self.config.languages();
         let prog_type = match &args.prog_type {
-            Some(p) => ProgType::from_string(p),
-            None => ProgType::new()
+            Some(p) => languages.resolve(p)?.0,
+            None => match languages.detect(&path) {
+                Some(t) => {
+                    print_color!(Fgb->"Detected type", b->t.as_str(), Fgb->"for", b->&name);
+                    t
+                },
+                None => {
+                    print_color!(Fy->"Unable to detect type of", b->&name, Fy->"- defaulting to", b->ProgType::new().as_str());
+                    ProgType::new()
+                }
+            }
         };
 
-        let program = Program { name, description, project, prog_type, path, tags };
+        let program = Program { name, description, project, prog_type, path, tags, license: None, link: None };
+
+        if let Some(snippets) = &args.gitignore {
+            if !project {
+                return Err(BoilError::NotAProject(program.name))
+            }
+
+            // Without snippets the project gets the .gitignore new projects of its type are given
+            let contents = match snippets.is_empty() {
+                true => languages.get(&program.prog_type).ok()
+                    .and_then(|l| l.gitignore.as_ref())
+                    .map(|gi| Vars::new(&program, &self.author()).render(gi)),
+                false => Some(gitignore::compose(snippets)?)
+            };
+
+            match contents {
+                Some(c) => { extend_gitignore(&program.path, &c)?; },
+                None => print_color!(Fy->"No .gitignore for", b->program.prog_type.as_str(), Fy->"projects - pass snippets with --gitignore=... to add one"),
+            }
+        }
 
         let name = program.name.to_owned();
         self.config_mut().insert(program.name.to_owned(), program);
@@ -134,29 +435,214 @@ impl Boil {
     }
 
     fn add_new(&mut self, args: NewArgs) -> BoilResult<()>{
-        let program: Program = self.parse_new(&args)?;
+        let mut program: Program = self.parse_new(&args)?;
+        let languages = self.config.languages();
+        let lang = languages.get(&program.prog_type)?;
+        let mut vars = Vars::new(&program, &self.author());
+        vars.insert("lib", &args.lib.to_string());
 
-        if !program.path.try_exists()? {
-            if program.project {
-                create_project(&program.path, &program.prog_type)?;
-            } else {
-                create_program(&program.path, &program.prog_type)?;
-            }
-        } else {
+        if program.path.try_exists()? {
             return Err(BoilError::PathExists(program.path))
+        }
+
+        // The slug names the package in the scaffold's manifest, ie Cargo.toml
+        let slug = vars.get("slug").unwrap_or_default();
+        if program.project && slug.is_empty() {
+            return Err(BoilError::InvalidName(program.name, String::from("it needs at least one letter or digit")))
+        }
+        if program.project && program.prog_type == ProgType::from("rust") && slug.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(BoilError::InvalidName(program.name, String::from("crate names can't start with a digit")))
+        }
+
+        // A dry run previews git templates from the cache rather than fetching them
+        let resolve = match args.dry_run {
+            true => Template::resolve_cached,
+            false => Template::resolve,
+        };
+        let template = match &args.template {
+            Some(t) => Some(resolve(
+                t,
+                args.template_ref.as_deref(),
+                &self.config.defaults.template_path,
+                &self.config.defaults.cache_path
+            )?),
+            None => None
+        };
+
+        let answers = match &template {
+            Some(t) => answer(&t.prompts(), &args.set, self.interactive)?,
+            None => args.set.to_owned()
         };
+        for (key, val) in answers.iter() {
+            vars.insert(key, val);
+        }
+
+        let defaults = &self.config.defaults;
+
+        // Temp scripts are never kept in the config, so nothing would ever remove their link
+        if !program.project && !args.temp && !args.no_link && (args.link || defaults.link) {
+            let strip_ext = args.strip_ext || defaults.link_strip_ext;
+            let link = link_path(&program.path, &defaults.link_path, strip_ext);
+
+            if link.symlink_metadata().is_ok() {
+                return Err(BoilError::PathExists(link))
+            }
+            program.link = Some(link);
+        }
+
+        let commit = args.commit || defaults.git_commit;
+        let init_git = program.project && !args.no_git && (args.git || commit || defaults.git);
+
+        let mut plan = match (&template, program.project) {
+            (Some(t), _) => {
+                let mut plan = Plan::new(&program.path, true);
+                t.plan(&mut plan, &vars)?;
+                plan
+            },
+            (None, true) => plan_project(&program.path, lang, &vars),
+            (None, false) => plan_program(&program.path, lang, &vars),
+        };
+
+        if let Some(l) = &program.license {
+            plan_license(&mut plan, l, &vars)?;
+        }
+
+        if init_git {
+            plan_gitignore(&mut plan, lang, &vars);
+        }
+
+        if let Some(snippets) = &args.gitignore {
+            plan.add_file(PlannedFile::new(".gitignore", gitignore::compose(snippets)?));
+        }
+
+        let mut hooks = lang.hooks.clone().unwrap_or_default();
+        if let Some(t) = &template {
+            hooks.extend(t.hooks());
+        }
+
+        if args.dry_run {
+            return self.print_dry_run(&program, &plan, &hooks, &vars, init_git, commit, args.temp)
+        }
+
+        // Anything created before a failure is removed again so a retry doesn't hit PathExists
+        let mut rollback = Rollback::default();
+
+        if let Err(e) = self.create_new(&program, &plan, &hooks, &vars, init_git, commit, &mut rollback) {
+            rollback.undo();
+            return Err(e)
+        }
+
+        let path = program.path.clone();
 
         if !args.temp {
             let name = program.name.to_owned();
-            let path = program.path.clone();
             self.config_mut().insert(name.to_owned(), program);
             print_color!(Fgb->"Successfully added", b->&name, Fgb->"to config");
             println!("{}", path.to_string_lossy());
         } else {
-            println!("{}", &program.path.to_string_lossy());
+            println!("{}", &path.to_string_lossy());
             self.config.temp = program
         }
 
+        // The entry is already created, so failing to open it only warrants a warning.
+        // The config is saved first, the editor may stay open for a long time
+        if args.open {
+            self.release()?;
+            if let Err(e) = open(&path, lang) {
+                eprintln!("boil warning: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_new(
+        &self,
+        program: &Program,
+        plan: &Plan,
+        hooks: &[String],
+        vars: &Vars,
+        init_git: bool,
+        commit: bool,
+        rollback: &mut Rollback
+    ) -> BoilResult<()> {
+        plan.write(rollback)?;
+
+        if init_git {
+            git::init(&program.path)?;
+        }
+
+        if let Err(e) = run_hooks(hooks, program, vars) {
+            if self.config.defaults.hook_rollback {
+                return Err(e)
+            }
+            eprintln!("boil warning: {e}");
+        }
+
+        if init_git && commit {
+            git::commit_all(&program.path, "Initial commit")?;
+        }
+
+        if let Some(link) = &program.link {
+            link_program(&program.path, link, rollback)?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn print_dry_run(
+        &self,
+        program: &Program,
+        plan: &Plan,
+        hooks: &[String],
+        vars: &Vars,
+        init_git: bool,
+        commit: bool,
+        temp: bool
+    ) -> BoilResult<()> {
+        print_color!(b->"Dry run - nothing will be created or added to the config");
+        println!();
+
+        print_color!(Fgb->"Files:");
+        print!("{}", plan.tree());
+
+        if init_git {
+            println!();
+            print_color!(Fgb->"Git:");
+            println!("git init");
+            if commit {
+                println!("git commit -m \"Initial commit\"");
+            }
+        }
+
+        if !hooks.is_empty() {
+            println!();
+            print_color!(Fgb->"Hooks:");
+            for hook in hooks.iter() {
+                println!("{}", render_hook(hook, vars));
+            }
+        }
+
+        if let Some(link) = &program.link {
+            println!();
+            print_color!(Fgb->"Link:");
+            println!("{} -> {}", link.to_string_lossy(), program.path.to_string_lossy());
+        }
+
+        println!();
+        print_color!(Fgb->"Config entry:");
+        // Shown in the format the config is kept in
+        let format = Format::of(&self.cfg_path)?;
+        let entry = if temp {
+            format.dump(&BTreeMap::from([("temp", program)]))?
+        } else {
+            let programs = BTreeMap::from([(program.name.as_str(), program)]);
+            format.dump(&BTreeMap::from([("programs", programs)]))?
+        };
+        print!("{}", entry);
+
         Ok(())
     }
 
@@ -200,26 +686,35 @@ impl Boil {
             (false, false, None) => self.config.defaults.bin_path.to_owned()
         };
         
-        let prog_type = match &args.prog_type {
-            Some(p) => ProgType::from_string(p),
-            None => ProgType::new()
+        let languages = self.config.languages();
+        let (prog_type, lang) = match &args.prog_type {
+            Some(p) => languages.resolve(p)?,
+            None => (ProgType::new(), languages.get(&ProgType::new())?)
         };
         
         if !args.project {
             path.push(&name);
-            path.set_extension(prog_type.ext());
+            if let Some(ext) = lang.ext() {
+                path.set_extension(ext);
+            }
         };
 
         let description = args.description.to_owned();
         let tags = args.tags.to_owned();
+        let license = args.license.to_owned();
 
-        Ok(Program { name, project: args.project, prog_type, path, description, tags })
+        Ok(Program { name, project: args.project, prog_type, path, description, tags, license, link: None })
     }
 
     fn edit(&mut self, args: EditArgs) -> BoilResult<()> {
         if !self.config.exists(&args.name) {
             return Err(BoilError::NotFound(args.name))
         }
+
+        let prog_type = match &args.eopts.prog_type {
+            Some(p) => Some(self.config.languages().resolve(p)?.0),
+            None => None
+        };
         
         let entry: &mut Program = self.config.get_mut(&args.name);
 
@@ -245,8 +740,12 @@ impl Boil {
             }
         }
 
-        if let Some(p) = args.eopts.prog_type {
-            entry.prog_type = ProgType::from_string(&p);
+        if let Some(p) = prog_type {
+            entry.prog_type = p;
+        }
+
+        if let Some(l) = args.eopts.license {
+            entry.license = Some(l);
         }
 
         print_color!(Fgb->"Successfully updated", b->args.name.as_str());
@@ -260,7 +759,7 @@ impl Boil {
             return Ok(())
         }
         let mut table = BoilTable::from_args(args)?;
-        table.display(self.config.values());
+        table.display(self.config.values(), &self.config.languages());
         
         
         Ok(())
@@ -278,83 +777,340 @@ impl Boil {
             }
         }
 
+        let program = self.config.get(args.name.to_owned()).cloned();
+
         self.config.remove(args.name.to_owned())?;
 
+        // The link is only removed while it still points at the entry, it may have been replaced since
+        if let Some((l, path)) = program.and_then(|p| Some((p.link?, p.path))) {
+            match fs::read_link(&l) {
+                Ok(target) if target == path => {
+                    fs::remove_file(&l)?;
+                    print_color!(Fgb->"Removed link", b->l.to_string_lossy());
+                },
+                Ok(_) => print_color!(Fy->"Left", b->l.to_string_lossy(), Fy->"alone - it no longer links to", b->&args.name),
+                Err(_) => {}
+            }
+        }
+
         print_color!(Fgb->"Successfully removed", b->&args.name, Fgb->"from config");
         Ok(())
     }
 
-    fn get_new_name(&self) -> String {
-        format!("boil{}", self.config.len())
-    }
+    fn open(&mut self, args: OpenArgs) -> BoilResult<()> {
+        let program = self.config.get(args.name.to_owned()).ok_or_else(|| BoilError::NotFound(args.name.to_owned()))?;
+        let path = program.path.to_owned();
+        let languages = self.config.languages();
+        let lang = languages.get(&program.prog_type)?;
 
-    fn config_mut(&mut self) -> &mut ProgMap {
-        &mut self.config.programs.0
+        self.release()?;
+        open(&path, lang)
     }
 
-    pub fn write(&self) -> BoilResult<()> {
-        self.config.write(&self.cfg_path)?;
-        Ok(())
-    }
+    fn gitignore(&self, args: GitignoreArgs) -> BoilResult<()> {
+        if args.list {
+            for name in gitignore::names() {
+                println!("{}", name);
+            }
+            return Ok(())
+        }
 
-}
+        let contents = gitignore::compose(&args.snippets)?;
 
-#[cfg(test)]
-mod config_tests {
-    use self::args::EditOptsGroup;
+        let Some(name) = args.entry else {
+            print!("{}", contents);
+            return Ok(())
+        };
 
-    use super::*;
-    use std::{env, str::FromStr};
-    use tempfile::{tempfile, TempDir, tempdir};
-    use rstest::*;
-    use args::InitArgs;
-    
+        let program = self.config.get(name.to_owned()).ok_or_else(|| BoilError::NotFound(name.to_owned()))?;
 
-    #[fixture]
-    fn temp_dir() -> TempDir {
-        let dir = tempdir().unwrap();
-        dir
-    }
+        if !program.project {
+            return Err(BoilError::NotAProject(name))
+        }
 
-    #[fixture]
-    fn config(temp_dir: TempDir) -> TempDir {
-        let mut dir_path = PathBuf::from(temp_dir.path().to_owned());
-        let mut config = Config::default();
-        config.defaults.proj_path = dir_path.to_owned();
-        let descriptions = [
-            Some(String::from("Fun program")),
-            Some(String::from("Utility program")),
-            None
-        ];
-        let tag_list = [
-            Some(vec!["Util".to_string(), "fun".to_string(),]),
-            Some(vec!["Wonderful".to_string(), "Fun".to_string(),]),
-            Some(vec!["util".to_string(), "Other".to_string(),])
-        ];
+        let path = extend_gitignore(&program.path, &contents)?;
+        print_color!(Fgb->"Updated", b->&path.to_string_lossy());
 
-        dir_path.push("null");
+        Ok(())
+    }
 
-        for i in 0..3 {
-            let name = format!("test{}", i);
-            dir_path.set_file_name(&name);
-            let project = false;
-            let path = dir_path.to_owned();
-            let prog_type = ProgType::Python;
-            let description = descriptions[i].to_owned();
-            let tags = tag_list[i].to_owned();
+    fn template(&self, args: TemplateArgs) -> BoilResult<()> {
+        let dir = &self.config.defaults.template_path;
 
-            let program = Program {
-                name: name.to_owned(),
-                project,
-                path,
-                prog_type,
-                description,
-                tags
-            };
+        match args.command {
+            TemplateCommands::List => {
+                let templates = Template::list(dir)?;
 
-            config.insert(name, program);
-            fs::File::create(&dir_path);
-        }
+                if templates.is_empty() {
+                    print_color!(Fy->"No templates found in", b->dir.to_string_lossy());
+                }
+
+                for t in templates.iter() {
+                    print_color!(b->&t.name);
+                    if let Some(d) = &t.manifest.description {
+                        println!("    {}", d);
+                    }
+                }
+            },
+            TemplateCommands::Show { name } => {
+                let t = Template::resolve(&name, None, dir, &self.config.defaults.cache_path)?;
+                let mut plan = Plan::new(Path::new(&t.name), true);
+                t.plan_all(&mut plan)?;
+
+                print_color!(Fgb->"Template", b->&t.name, Fgb->"at", b->t.path.to_string_lossy());
+                if let Some(d) = &t.manifest.description {
+                    println!("{}", d);
+                }
+                if !t.manifest.extends.is_empty() {
+                    print_color!(Fgb->"Extends", b->t.manifest.extends.join(", "));
+                }
+
+                println!();
+                print_color!(Fgb->"Files:");
+                print!("{}", plan.tree());
+
+                let vars = t.variables()?;
+                if !vars.is_empty() {
+                    println!();
+                    print_color!(Fgb->"Variables:");
+                    for v in vars {
+                        println!("{{{{{}}}}}", v);
+                    }
+                }
+
+                let prompts = t.prompts();
+                if !prompts.is_empty() {
+                    println!();
+                    print_color!(Fgb->"Prompts:");
+                    for p in prompts {
+                        let message = p.message.as_deref().unwrap_or_default();
+                        let default = p.default_value()?;
+                        let kind = format!("{:?}", p.kind).to_lowercase();
+                        println!("{} ({}, default '{}') {}", p.name, kind, default, message);
+                    }
+                }
+
+                let conditions: BTreeMap<&String, &String> = t.manifest.when.iter().collect();
+                if !conditions.is_empty() {
+                    println!();
+                    print_color!(Fgb->"Included when:");
+                    for (path, cond) in conditions {
+                        println!("{} - {}", path, cond);
+                    }
+                }
+
+                let hooks = t.hooks();
+                if !hooks.is_empty() {
+                    println!();
+                    print_color!(Fgb->"Hooks:");
+                    for h in hooks {
+                        println!("{}", h);
+                    }
+                }
+            },
+            TemplateCommands::Add { name, dir: src } => {
+                let t = Template::add(dir, &name, &src)?;
+                print_color!(Fgb->"Successfully added template", b->&t.name);
+                println!("{}", t.path.to_string_lossy());
+            },
+            TemplateCommands::Capture { entry, name } => {
+                let program = self.config.get(entry.to_owned()).ok_or_else(|| BoilError::NotFound(entry.to_owned()))?;
+
+                if !program.project {
+                    return Err(BoilError::NotAProject(entry))
+                }
+
+                let t = Template::capture(dir, &name, &program.path, &program.name)?;
+                print_color!(Fgb->"Successfully captured", b->&entry, Fgb->"as template", b->&t.name);
+                println!("{}", t.path.to_string_lossy());
+            },
+            TemplateCommands::Remove { force, name } => {
+                let t = Template::find(dir, &name)?;
+
+                if !force && !user_input(colorize!(b->"Delete template", bFg->&t.name, b->"and all of its files - [y/N]"))? {
+                    return Ok(())
+                }
+
+                fs::remove_dir_all(&t.path)?;
+                print_color!(Fgb->"Successfully removed template", b->&t.name);
+            },
+            TemplateCommands::Export { name, dest } => {
+                let t = Template::resolve(&name, None, dir, &self.config.defaults.cache_path)?;
+                t.export(&dest)?;
+                print_color!(Fgb->"Exported template", b->&t.name, Fgb->"to", b->dest.to_string_lossy());
+            },
+        }
+
+        Ok(())
+    }
+
+    fn author(&self) -> String {
+        match &self.config.defaults.author {
+            Some(a) => a.to_owned(),
+            None => default_author()
+        }
+    }
+
+    fn retype(&mut self, args: RetypeArgs) -> BoilResult<()> {
+        let languages = self.config.languages();
+
+        let names = if args.names.is_empty() {
+            let mut all: Vec<String> = self.config.iter().map(|(k, _)| k.to_owned()).collect();
+            all.sort();
+            all
+        } else {
+            args.names
+        };
+
+        let set_type = match &args.prog_type {
+            Some(p) => Some(languages.resolve(p)?.0),
+            None => None
+        };
+
+        for name in names.iter() {
+            if !self.config.exists(name) {
+                return Err(BoilError::NotFound(name.to_owned()))
+            }
+        }
+
+        for name in names.iter() {
+            let entry = self.config.get_mut(name);
+
+            let new_type = match &set_type {
+                Some(t) => t.to_owned(),
+                None => match languages.detect(&entry.path) {
+                    Some(t) => t,
+                    None => {
+                        print_color!(Fy->"Unable to detect type of", b->name);
+                        continue
+                    }
+                }
+            };
+
+            if new_type != entry.prog_type {
+                print_color!(b->name, Fgb->"-", b->entry.prog_type.as_str(), Fgb->"->", b->new_type.as_str());
+                entry.prog_type = new_type;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_new_name(&self) -> String {
+        format!("boil{}", self.config.len())
+    }
+
+    fn config_mut(&mut self) -> &mut ProgMap {
+        &mut self.config.programs.0
+    }
+
+    /// Writes any changes to the config (unless it was only loaded to read it) and gives up
+    /// the lock, so other commands can go ahead while this one waits on an editor.
+    /// The config isn't written again afterwards
+    fn release(&mut self) -> BoilResult<()> {
+        if !self.lock.as_ref().is_some_and(|l| l.shared) {
+            self.write()?;
+        }
+
+        self.lock = None;
+        self.released = true;
+        Ok(())
+    }
+
+    /// Writes the config back, unless something other than boil changed it since it was
+    /// loaded. Other boil commands are kept out by the lock instead
+    pub fn write(&mut self) -> BoilResult<()> {
+        if self.released {
+            return Ok(())
+        }
+
+        if let Some(loaded) = self.loaded {
+            if digest(&self.cfg_path).ok() != Some(loaded) {
+                return Err(BoilError::ConfigModified(self.cfg_path.to_owned()))
+            }
+        }
+
+        self.config.write(&self.cfg_path)?;
+
+        if self.loaded.is_some() {
+            self.loaded = Some(digest(&self.cfg_path)?);
+        }
+        Ok(())
+    }
+
+}
+
+/// Digest of the file at `path`, to tell whether it changed
+fn digest(path: &Path) -> BoilResult<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(path)?.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod config_tests {
+    use self::args::EditOptsGroup;
+
+    use super::*;
+    use std::{env, str::FromStr};
+    use tempfile::{tempfile, TempDir, tempdir};
+    use rstest::*;
+    use args::InitArgs;
+    use template::{Prompt, PromptKind};
+    
+
+    #[fixture]
+    fn temp_dir() -> TempDir {
+        let dir = tempdir().unwrap();
+        dir
+    }
+
+    /// Arguments of `boil new <name>`, for tests to set only what they check
+    fn new_args(name: &str) -> NewArgs {
+        NewArgs { name: Some(name.to_owned()), ..NewArgs::default() }
+    }
+
+    #[fixture]
+    fn config(temp_dir: TempDir) -> TempDir {
+        let mut dir_path = PathBuf::from(temp_dir.path().to_owned());
+        let mut config = Config::default();
+        config.defaults.proj_path = dir_path.to_owned();
+        let descriptions = [
+            Some(String::from("Fun program")),
+            Some(String::from("Utility program")),
+            None
+        ];
+        let tag_list = [
+            Some(vec!["Util".to_string(), "fun".to_string(),]),
+            Some(vec!["Wonderful".to_string(), "Fun".to_string(),]),
+            Some(vec!["util".to_string(), "Other".to_string(),])
+        ];
+
+        dir_path.push("null");
+
+        for i in 0..3 {
+            let name = format!("test{}", i);
+            dir_path.set_file_name(&name);
+            let project = false;
+            let path = dir_path.to_owned();
+            let prog_type = ProgType::from("python");
+            let description = descriptions[i].to_owned();
+            let tags = tag_list[i].to_owned();
+
+            let program = Program {
+                name: name.to_owned(),
+                project,
+                path,
+                prog_type,
+                description,
+                tags,
+                license: None,
+                link: None
+            };
+
+            config.insert(name, program);
+            fs::File::create(&dir_path);
+        }
 
         dir_path.set_file_name("config.toml");
         config.write(&dir_path).unwrap();
@@ -396,17 +1152,80 @@ mod config_tests {
         let prog_type = Some("Rust".to_string());
         let path = path.to_owned();
 
-        boil.add_existing(AddArgs{name: name.to_owned(), description: description.to_owned(), tags: tags.to_owned(), prog_type: prog_type.to_owned(), path: path.to_owned()}).unwrap();
+        boil.add_existing(AddArgs{name: name.to_owned(), description: description.to_owned(), tags: tags.to_owned(), prog_type: prog_type.to_owned(), gitignore: None, path: path.to_owned()}).unwrap();
 
         let entry = boil.config.get(String::from("test4")).unwrap();
 
         assert_eq!(name, entry.name);
         assert_eq!(description, entry.description);
         assert_eq!(tags, entry.tags);
-        assert_eq!(ProgType::Rust, entry.prog_type);
+        assert_eq!(ProgType::from("rust"), entry.prog_type);
         assert_eq!(path, entry.path);
     }
 
+    #[rstest]
+    fn test_detect_type(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        let dir = config.path();
+
+        fs::write(dir.join("tool"), "#!/usr/bin/env -S python3.12 -u\nprint()").unwrap();
+        fs::write(dir.join("run.sh"), "echo").unwrap();
+        fs::write(dir.join("app.js"), "").unwrap();
+        fs::create_dir_all(dir.join("ts-proj")).unwrap();
+        fs::write(dir.join("ts-proj/package.json"), "{}").unwrap();
+        fs::write(dir.join("ts-proj/tsconfig.json"), "{}").unwrap();
+        fs::create_dir_all(dir.join("js-proj")).unwrap();
+        fs::write(dir.join("js-proj/package.json"), "{}").unwrap();
+        fs::create_dir_all(dir.join("go-proj")).unwrap();
+        fs::write(dir.join("go-proj/go.mod"), "module x").unwrap();
+
+        let cases = [
+            ("tool", "python"),
+            ("run.sh", "bash"),
+            ("app.js", "javascript"),
+            ("ts-proj", "typescript"),
+            ("js-proj", "javascript"),
+            ("go-proj", "go"),
+        ];
+
+        for (file, expected) in cases {
+            let args = AddArgs {
+                name: file.to_string(),
+                description: None,
+                tags: None,
+                prog_type: None,
+                gitignore: None,
+                path: dir.join(file)
+            };
+            boil.add_existing(args).unwrap();
+            assert_eq!(ProgType::from(expected), boil.config.get(file.to_string()).unwrap().prog_type, "{}", file);
+        }
+
+        // --type overrides detection
+        let args = AddArgs {
+            name: String::from("forced"),
+            description: None,
+            tags: None,
+            prog_type: Some(String::from("ruby")),
+            gitignore: None,
+            path: dir.join("go-proj")
+        };
+        boil.add_existing(args).unwrap();
+        assert_eq!(ProgType::from("ruby"), boil.config.get(String::from("forced")).unwrap().prog_type);
+
+        // Re-detecting fixes mislabeled entries, the fixture's files are empty so they are left alone
+        boil.retype(RetypeArgs { detect: true, prog_type: None, names: vec![] }).unwrap();
+        assert_eq!(ProgType::from("go"), boil.config.get(String::from("forced")).unwrap().prog_type);
+        assert_eq!(ProgType::from("python"), boil.config.get(String::from("test0")).unwrap().prog_type);
+
+        boil.retype(RetypeArgs { detect: false, prog_type: Some(String::from("rb")), names: vec![String::from("test0")] }).unwrap();
+        assert_eq!(ProgType::from("ruby"), boil.config.get(String::from("test0")).unwrap().prog_type);
+
+        assert!(boil.retype(RetypeArgs { detect: true, prog_type: None, names: vec![String::from("missing")] }).is_err());
+    }
+
     #[rstest]
     fn test_add_new(config: TempDir) {
         let mut path = PathBuf::from(config.path());
@@ -420,13 +1239,11 @@ mod config_tests {
         let project = true;
 
         let mut args = NewArgs {
-            name: Some(name.to_owned()),
             description: description.to_owned(),
-            temp: false,
             project: true,
             prog_type: prog_type.to_owned(),
             tags: tags.to_owned(),
-            path: None
+            ..new_args(&name)
         };
 
         boil.add_new(args.clone()).unwrap();
@@ -439,7 +1256,7 @@ mod config_tests {
         assert_eq!(name, entry.name);
         assert_eq!(description, entry.description);
         assert_eq!(tags, entry.tags);
-        assert_eq!(ProgType::Bash, entry.prog_type);
+        assert_eq!(ProgType::from("bash"), entry.prog_type);
         assert_eq!(path, entry.path);
         assert_eq!(true, entry.project);
 
@@ -459,6 +1276,1223 @@ mod config_tests {
 
     }
 
+    #[rstest]
+    fn test_add_new_dry_run(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        boil.config.defaults.bin_path = config.path().to_path_buf();
+        boil.config.defaults.link_path = config.path().join("links");
+
+        let mut args = NewArgs {
+            project: true,
+            prog_type: Some(String::from("rust")),
+            git: true,
+            license: Some(String::from("MIT")),
+            dry_run: true,
+            ..new_args("dry")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("dry");
+        assert!(!path.exists());
+        assert!(!boil.config.exists("dry"));
+
+        args.project = false;
+        args.link = true;
+        args.git = false;
+        args.license = None;
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("dry.rs");
+        assert!(!path.exists());
+        assert!(!config.path().join("links").exists());
+        assert!(!boil.config.exists("dry"));
+
+        // Same path rules as a real run
+        args.name = Some(String::from("test0"));
+        assert!(boil.add_new(args).is_err());
+    }
+
+    #[rstest]
+    fn test_add_new_template(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let templates = config.path().join("templates");
+        fs::create_dir_all(templates.join("service/src")).unwrap();
+        fs::write(templates.join("service/Makefile"), "all:").unwrap();
+        fs::write(templates.join("service/src/main.py"), "print()").unwrap();
+        fs::create_dir_all(templates.join("service/{{name}}")).unwrap();
+        fs::write(templates.join("service/{{name}}/README.md"), "# {{ name }} by {{author}}\n{{description}}\n{{unknown}}").unwrap();
+        boil.config.defaults.template_path = templates;
+        boil.config.defaults.author = Some(String::from("Jane"));
+
+        let mut args = NewArgs {
+            description: Some(String::from("My service")),
+            project: true,
+            template: Some(String::from("service")),
+            ..new_args("svc")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("svc");
+
+        assert_eq!("all:", fs::read_to_string(path.join("Makefile")).unwrap());
+        assert_eq!("print()", fs::read_to_string(path.join("src/main.py")).unwrap());
+        assert_eq!(
+            "# svc by Jane\nMy service\n{{unknown}}",
+            fs::read_to_string(path.join("svc/README.md")).unwrap()
+        );
+
+        // Missing or invalid template names
+        args.name = Some(String::from("svc2"));
+        args.template = Some(String::from("missing"));
+        assert!(boil.add_new(args.clone()).is_err());
+
+        args.template = Some(String::from("../service"));
+        assert!(boil.add_new(args.clone()).is_err());
+    }
+
+    #[rstest]
+    fn test_add_new_template_layers(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let templates = config.path().join("templates");
+        for t in ["base", "python/src", "cli/src", "loop"] {
+            fs::create_dir_all(templates.join(t)).unwrap();
+        }
+        fs::write(templates.join("base/README.md"), "# {{name}}").unwrap();
+        fs::write(templates.join("base/.gitignore"), "*.log\n.env\n").unwrap();
+        fs::write(templates.join("base/boil.toml"), "hooks = [\"echo base >> hooks.txt\"]").unwrap();
+        fs::write(templates.join("python/src/main.py"), "print()").unwrap();
+        fs::write(templates.join("python/.gitignore"), "__pycache__/\n*.log\n").unwrap();
+        // base is reached through both cli and python, but only applied once
+        fs::write(templates.join("python/boil.toml"), "extends = [\"base\"]").unwrap();
+        fs::write(templates.join("cli/src/main.py"), "import argparse").unwrap();
+        fs::write(templates.join("cli/boil.toml"), r#"
+extends = ["base", "python"]
+hooks = ["echo cli >> hooks.txt"]
+"#).unwrap();
+        fs::write(templates.join("loop/boil.toml"), "extends = [\"../loop\"]").unwrap();
+        boil.config.defaults.template_path = templates.to_owned();
+
+        let mut args = NewArgs {
+            project: true,
+            template: Some(String::from("cli")),
+            ..new_args("layered")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("layered");
+
+        assert_eq!("# layered", fs::read_to_string(path.join("README.md")).unwrap());
+        assert_eq!("import argparse", fs::read_to_string(path.join("src/main.py")).unwrap());
+        assert_eq!("*.log\n.env\n\n__pycache__/\n", fs::read_to_string(path.join(".gitignore")).unwrap());
+        assert_eq!("base\ncli\n", fs::read_to_string(path.join("hooks.txt")).unwrap());
+        assert!(!path.join("boil.toml").exists());
+
+        let cli = Template::resolve("cli", None, &templates, config.path()).unwrap();
+        assert_eq!(vec!["echo base >> hooks.txt", "echo cli >> hooks.txt"], cli.hooks());
+
+        // Exported with its layers applied, so it needs nothing else
+        let dest = config.path().join("exported");
+        cli.export(&dest).unwrap();
+        let exported = Template::resolve(&dest.to_string_lossy(), None, &templates, config.path()).unwrap();
+        assert!(exported.manifest.extends.is_empty());
+        assert_eq!(cli.hooks(), exported.hooks());
+        assert_eq!("# {{name}}", fs::read_to_string(dest.join("README.md")).unwrap());
+        assert_eq!("import argparse", fs::read_to_string(dest.join("src/main.py")).unwrap());
+        assert_eq!("*.log\n.env\n\n__pycache__/\n", fs::read_to_string(dest.join(".gitignore")).unwrap());
+        assert!(matches!(cli.export(&dest), Err(BoilError::PathExists(_))));
+
+        args.name = Some(String::from("loop"));
+        args.template = Some(String::from("loop"));
+        assert!(matches!(boil.add_new(args), Err(BoilError::TemplateCycle(_))));
+    }
+
+    #[rstest]
+    fn test_gitignore(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let composed = gitignore::compose(&["rust", "c", "rs"]).unwrap();
+        assert!(composed.starts_with("# --- rust ---\n"));
+        assert!(composed.contains("# --- c ---\n"));
+        assert_eq!(1, composed.matches("# --- rust ---").count());
+        assert_eq!(1, composed.lines().filter(|l| *l == "*.pdb").count());
+        assert!(gitignore::compose(&["rust", "cobol"]).is_err());
+
+        let args = NewArgs {
+            project: true,
+            prog_type: Some(String::from("rust")),
+            gitignore: Some(vec![String::from("vscode"), String::from("linux")]),
+            ..new_args("ignored")
+        };
+
+        boil.add_new(args).unwrap();
+        path.set_file_name("ignored");
+        // Merged into the language's own .gitignore
+        let text = fs::read_to_string(path.join(".gitignore")).unwrap();
+        assert!(text.starts_with("# Build output\n/target\n"));
+        assert!(text.contains(".vscode/*") && text.contains(".Trash-*"));
+
+        // Existing projects only get the missing patterns
+        let existing = config.path().join("existing");
+        fs::create_dir(&existing).unwrap();
+        fs::write(existing.join("Cargo.toml"), "").unwrap();
+        fs::write(existing.join(".gitignore"), "/target\n").unwrap();
+        boil.add_existing(AddArgs {
+            name: String::from("existing"),
+            description: None,
+            tags: None,
+            prog_type: None,
+            gitignore: Some(vec![]),
+            path: existing.to_owned()
+        }).unwrap();
+        let text = fs::read_to_string(existing.join(".gitignore")).unwrap();
+        assert!(text.starts_with("/target\n\n# Build output\n"));
+        assert_eq!(1, text.matches("/target").count());
+
+        // Types without a bundled snippet go by their language's .gitignore, if it has one
+        for (name, file, prog_type) in [("existing-go", "go.mod", "go"), ("existing-sh", "run.sh", "bash")] {
+            let dir = config.path().join(name);
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join(file), "").unwrap();
+            boil.add_existing(AddArgs {
+                name: name.to_owned(),
+                description: None,
+                tags: None,
+                prog_type: Some(prog_type.to_owned()),
+                gitignore: Some(vec![]),
+                path: dir.to_owned()
+            }).unwrap();
+        }
+        assert!(fs::read_to_string(config.path().join("existing-go/.gitignore")).unwrap().starts_with("/existing-go\n"));
+        assert!(!config.path().join("existing-sh/.gitignore").exists());
+
+        boil.gitignore(GitignoreArgs {
+            list: false,
+            entry: Some(String::from("existing")),
+            snippets: vec![String::from("rust"), String::from("macos")]
+        }).unwrap();
+        let extended = fs::read_to_string(existing.join(".gitignore")).unwrap();
+        assert!(extended.starts_with(&text));
+        assert!(extended.contains(".DS_Store"));
+        assert_eq!(1, extended.matches("/target").count());
+
+        // Only projects have a .gitignore
+        let res = boil.gitignore(GitignoreArgs { list: false, entry: Some(String::from("test0")), snippets: vec![] });
+        assert!(matches!(res, Err(BoilError::NotAProject(_))));
+    }
+
+    #[rstest]
+    fn test_add_new_template_prompts(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let templates = config.path().join("templates");
+        fs::create_dir_all(templates.join("svc/docker")).unwrap();
+        fs::write(templates.join("svc/Dockerfile"), "FROM python:{{python}}\nEXPOSE {{port}}").unwrap();
+        fs::write(templates.join("svc/docker/compose.yml"), "").unwrap();
+        fs::write(templates.join("svc/setup.cfg"), "").unwrap();
+        fs::write(templates.join("svc/README.md"), "{{name}} on {{python}} by {{team}}").unwrap();
+        fs::write(templates.join("svc/boil.toml"), r#"
+[[prompts]]
+name = "docker"
+message = "Include a Dockerfile?"
+type = "bool"
+
+[[prompts]]
+name = "python"
+choices = ["3.11", "3.12"]
+default = "3.12"
+
+[[prompts]]
+name = "port"
+type = "number"
+default = "8000"
+
+[when]
+"Dockerfile" = "docker"
+"docker/" = "docker"
+"setup.cfg" = "python != 3.12"
+"#).unwrap();
+        boil.config.defaults.template_path = templates;
+
+        let mut args = NewArgs {
+            project: true,
+            template: Some(String::from("svc")),
+            set: vec![
+                (String::from("docker"), String::from("yes")),
+                (String::from("python"), String::from("3.12")),
+                (String::from("port"), String::from("8080")),
+                (String::from("team"), String::from("platform")),
+            ],
+            ..new_args("svc")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("svc");
+        assert_eq!("FROM python:3.12\nEXPOSE 8080", fs::read_to_string(path.join("Dockerfile")).unwrap());
+        assert_eq!("svc on 3.12 by platform", fs::read_to_string(path.join("README.md")).unwrap());
+        assert!(path.join("docker/compose.yml").exists());
+        assert!(!path.join("setup.cfg").exists());
+
+        args.name = Some(String::from("svc2"));
+        args.set = vec![(String::from("docker"), String::from("no")), (String::from("python"), String::from("3.11"))];
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("svc2");
+        assert!(!path.join("Dockerfile").exists());
+        assert!(!path.join("docker").exists());
+        assert!(path.join("setup.cfg").exists());
+
+        // Answers must fit the prompt
+        for (key, val) in [("python", "2.7"), ("docker", "maybe"), ("port", "http")] {
+            args.name = Some(format!("svc-{}", key));
+            args.set = vec![(key.to_owned(), val.to_owned())];
+            assert!(matches!(boil.add_new(args.clone()), Err(BoilError::InvalidAnswer(_, _))));
+        }
+
+        let prompt = Prompt { name: String::from("docker"), message: None, kind: PromptKind::Bool, default: None, choices: vec![] };
+        assert_eq!("false", prompt.default_value().unwrap());
+        assert_eq!("true", prompt.validate("Y").unwrap());
+
+        // Nothing is asked unless told it can be, whatever stdin is
+        let answers = answer(&[prompt], &[(String::from("team"), String::from("qa"))], false).unwrap();
+        assert_eq!(vec![(String::from("docker"), String::from("false")), (String::from("team"), String::from("qa"))], answers);
+    }
+
+    #[rstest]
+    fn test_template_command(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        let templates = config.path().join("templates");
+        boil.config.defaults.template_path = templates.to_owned();
+
+        let src = config.path().join("src-layout");
+        fs::create_dir_all(src.join("{{name}}")).unwrap();
+        fs::create_dir_all(src.join(".git")).unwrap();
+        fs::write(src.join("{{name}}/main.py"), "# {{description}} by {{ author }}").unwrap();
+        fs::write(src.join("boil.toml"), "description = \"Layout\"\nhooks = [\"echo {{name}} {{port}}\"]").unwrap();
+
+        assert!(Template::list(&templates).unwrap().is_empty());
+        boil.template(TemplateArgs { command: TemplateCommands::Add { name: String::from("layout"), dir: src.to_owned() } }).unwrap();
+        assert!(templates.join("layout/{{name}}/main.py").exists());
+        assert!(!templates.join("layout/.git").exists());
+
+        // Names are unique and stay inside of the template directory
+        let add = |name: &str| TemplateArgs { command: TemplateCommands::Add { name: name.to_owned(), dir: src.to_owned() } };
+        assert!(matches!(boil.template(add("layout")), Err(BoilError::PathExists(_))));
+        assert!(matches!(boil.template(add("../layout")), Err(BoilError::InvalidTemplate(_))));
+
+        // A broken template is left out of the list rather than failing it
+        fs::create_dir_all(templates.join("broken")).unwrap();
+        fs::write(templates.join("broken/boil.toml"), "extends = 1").unwrap();
+
+        let listed = Template::list(&templates).unwrap();
+        assert_eq!(vec!["layout"], listed.iter().map(|t| t.name.as_str()).collect::<Vec<&str>>());
+        assert_eq!(Some(String::from("Layout")), listed[0].manifest.description);
+        assert_eq!(vec!["name", "description", "author", "port"], listed[0].variables().unwrap());
+        boil.template(TemplateArgs { command: TemplateCommands::Show { name: String::from("layout") } }).unwrap();
+
+        let dest = config.path().join("exported");
+        boil.template(TemplateArgs { command: TemplateCommands::Export { name: String::from("layout"), dest: dest.to_owned() } }).unwrap();
+        assert!(dest.join("boil.toml").exists());
+        assert!(dest.join("{{name}}/main.py").exists());
+
+        boil.template(TemplateArgs { command: TemplateCommands::Remove { force: true, name: String::from("layout") } }).unwrap();
+        assert!(!templates.join("layout").exists());
+        assert!(dest.exists());
+    }
+
+    #[rstest]
+    fn test_template_capture(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        let templates = config.path().join("templates");
+        boil.config.defaults.template_path = templates.to_owned();
+
+        let proj = config.path().join("my-app");
+        for dir in ["src/my_app", "target/debug", ".git", "logs", "docs/build"] {
+            fs::create_dir_all(proj.join(dir)).unwrap();
+        }
+        fs::write(proj.join(".gitignore"), "*.log\n/logs/\n!keep.log\ndocs/**/*.html\n").unwrap();
+        fs::write(proj.join("README.md"), "# my-app\nimport my_app\nsee amy-app, my-application and my_app_core").unwrap();
+        fs::write(proj.join("src/my_app/__init__.py"), "").unwrap();
+        fs::write(proj.join("debug.log"), "").unwrap();
+        fs::write(proj.join("keep.log"), "").unwrap();
+        fs::write(proj.join("logs/out.txt"), "").unwrap();
+        fs::write(proj.join("docs/index.md"), "").unwrap();
+        fs::write(proj.join("docs/page.html"), "").unwrap();
+        fs::write(proj.join("target/debug/my-app"), [0xff, 0xfe]).unwrap();
+        fs::write(proj.join(".git/HEAD"), "").unwrap();
+
+        boil.add_existing(AddArgs {
+            name: String::from("my-app"),
+            description: None,
+            tags: None,
+            prog_type: Some(String::from("python")),
+            gitignore: None,
+            path: proj.to_owned()
+        }).unwrap();
+
+        let capture = |entry: &str, name: &str| TemplateArgs {
+            command: TemplateCommands::Capture { entry: entry.to_owned(), name: name.to_owned() }
+        };
+        boil.template(capture("my-app", "app")).unwrap();
+
+        let t = templates.join("app");
+        // Only whole words are replaced
+        assert_eq!(
+            "# {{name}}\nimport {{package}}\nsee amy-app, my-application and my_app_core",
+            fs::read_to_string(t.join("README.md")).unwrap()
+        );
+        assert!(t.join("src/{{package}}/__init__.py").exists());
+        assert!(t.join(".gitignore").exists());
+        assert!(t.join("keep.log").exists());
+        assert!(t.join("docs/index.md").exists());
+        for skipped in ["debug.log", "logs", "target", ".git", "docs/page.html"] {
+            assert!(!t.join(skipped).exists(), "{}", skipped);
+        }
+
+        // The captured template creates the same project under a new name
+        let args = NewArgs {
+            project: true,
+            template: Some(String::from("app")),
+            ..new_args("other-app")
+        };
+        boil.add_new(args).unwrap();
+        path.set_file_name("other-app");
+        assert!(fs::read_to_string(path.join("README.md")).unwrap().starts_with("# other-app\nimport other_app\n"));
+        assert!(path.join("src/other_app/__init__.py").exists());
+
+        assert!(matches!(boil.template(capture("my-app", "app")), Err(BoilError::PathExists(_))));
+        assert!(matches!(boil.template(capture("test0", "script")), Err(BoilError::NotAProject(_))));
+
+        // The entry's name is quoted in the manifest written for it
+        boil.add_existing(AddArgs {
+            name: String::from("my \"app\""),
+            description: None,
+            tags: None,
+            prog_type: Some(String::from("python")),
+            gitignore: None,
+            path: proj.to_owned()
+        }).unwrap();
+        boil.template(capture("my \"app\"", "quoted")).unwrap();
+        let quoted = Template::find(&templates, "quoted").unwrap();
+        assert_eq!(Some(String::from("Captured from my \"app\"")), quoted.manifest.description);
+    }
+
+    #[rstest]
+    fn test_add_new_python(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let args = NewArgs {
+            description: Some(String::from("Does \"things\" \\o/")),
+            project: true,
+            prog_type: Some(String::from("python")),
+            ..new_args("My-Tool")
+        };
+
+        boil.config.defaults.author = Some(String::from("Jane \"JD\" Doe"));
+        boil.add_new(args).unwrap();
+        path.set_file_name("My-Tool");
+
+        let pyproject: toml::Table = toml::from_str(&fs::read_to_string(path.join("pyproject.toml")).unwrap()).unwrap();
+        assert_eq!("my-tool", pyproject["project"]["name"].as_str().unwrap());
+        assert_eq!("Does \"things\" \\o/", pyproject["project"]["description"].as_str().unwrap());
+        assert_eq!("Jane \"JD\" Doe", pyproject["project"]["authors"][0]["name"].as_str().unwrap());
+        assert_eq!("my_tool.__main__:main", pyproject["project"]["scripts"]["my-tool"].as_str().unwrap());
+
+        // Quotes in the description don't end the docstring early
+        let init = fs::read_to_string(path.join("src/my_tool/__init__.py")).unwrap();
+        assert_eq!(Some(r#""""Does \"things\" \\o/""""#), init.lines().next());
+        assert!(path.join("src/my_tool/__main__.py").exists());
+        assert!(path.join("README.md").exists());
+        assert!(path.join(".gitignore").exists());
+        assert!(fs::read_to_string(path.join("tests/test_my_tool.py")).unwrap().contains("from my_tool import"));
+    }
+
+    #[rstest]
+    fn test_add_new_git_template(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        boil.config.defaults.cache_path = config.path().join("cache");
+
+        // The identity is passed to each commit so the user's git config doesn't matter
+        let git = |dir: &Path, args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=boil", "-c", "user.email=boil@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+
+        // Shared template repository with a tagged first version
+        let work = config.path().join("work");
+        fs::create_dir_all(&work).unwrap();
+        git(&work, &["init", "--quiet"]);
+        fs::write(work.join("VERSION"), "1 {{name}}").unwrap();
+        git(&work, &["add", "--all"]);
+        git(&work, &["commit", "--quiet", "-m", "v1"]);
+        git(&work, &["tag", "v1"]);
+        fs::write(work.join("VERSION"), "2 {{name}}").unwrap();
+        git(&work, &["commit", "--quiet", "-am", "v2"]);
+        git(config.path(), &["clone", "--quiet", "--bare", "work", "svc.git"]);
+
+        let url = format!("file://{}", config.path().join("svc.git").to_string_lossy());
+
+        let mut args = NewArgs {
+            project: true,
+            template: Some(url),
+            ..new_args("latest")
+        };
+
+        // A dry run never fetches, so there is nothing to preview yet
+        let dry_run = NewArgs { name: Some(String::from("preview")), dry_run: true, ..args.clone() };
+        assert!(matches!(boil.add_new(dry_run.clone()), Err(BoilError::TemplateNotCached(_))));
+        assert!(!config.path().join("cache").exists());
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("latest");
+        assert_eq!("2 latest", fs::read_to_string(path.join("VERSION")).unwrap());
+        assert!(!path.join(".git").exists());
+        boil.add_new(dry_run.clone()).unwrap();
+
+        // Nor checks out another version
+        let pinned_dry_run = NewArgs { template_ref: Some(String::from("v1")), ..dry_run };
+        assert!(matches!(boil.add_new(pinned_dry_run.clone()), Err(BoilError::TemplateNotCached(_))));
+
+        args.name = Some(String::from("pinned"));
+        args.template_ref = Some(String::from("v1"));
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("pinned");
+        assert_eq!("1 pinned", fs::read_to_string(path.join("VERSION")).unwrap());
+        boil.add_new(pinned_dry_run).unwrap();
+
+        // Sources are never taken for git options
+        let res = Template::resolve("--upload-pack=touch pwned/x", None, config.path(), &boil.config.defaults.cache_path);
+        assert!(matches!(res, Err(BoilError::Git(_, e)) if e.contains("repository '--upload-pack=touch pwned/x'")));
+
+        // The cached clone is updated on later uses
+        fs::write(work.join("VERSION"), "3 {{name}}").unwrap();
+        git(&work, &["commit", "--quiet", "-am", "v3"]);
+        git(&work, &["push", "--quiet", "../svc.git", "HEAD"]);
+
+        args.name = Some(String::from("updated"));
+        args.template_ref = None;
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("updated");
+        assert_eq!("3 updated", fs::read_to_string(path.join("VERSION")).unwrap());
+
+        // A local checkout works as well
+        args.name = Some(String::from("local"));
+        args.template = Some(work.to_string_lossy().into_owned());
+        args.template_ref = Some(String::from("v1"));
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("local");
+        assert_eq!("1 local", fs::read_to_string(path.join("VERSION")).unwrap());
+    }
+
+    #[rstest]
+    fn test_add_new_rust(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let mut args = NewArgs {
+            description: Some(String::from("Say \"hi\" C:\\rust\n")),
+            project: true,
+            prog_type: Some(String::from("rust")),
+            ..new_args("My Crate")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("My Crate");
+
+        let manifest: toml::Table = toml::from_str(&fs::read_to_string(path.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!("my-crate", manifest["package"]["name"].as_str().unwrap());
+        assert_eq!("Say \"hi\" C:\\rust\n", manifest["package"]["description"].as_str().unwrap());
+        assert!(path.join("src/main.rs").exists());
+        assert!(path.join(".gitignore").exists());
+
+        args.name = Some(String::from("my-lib"));
+        args.lib = true;
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("my-lib");
+
+        assert!(path.join("src/lib.rs").exists());
+        assert!(!path.join("src/main.rs").exists());
+
+        // Names that don't make a valid crate name
+        for name in ["2fast", "!!!"] {
+            args.name = Some(String::from(name));
+            assert!(matches!(boil.add_new(args.clone()), Err(BoilError::InvalidName(_, _))));
+            path.set_file_name(name);
+            assert!(!path.exists());
+        }
+    }
+
+    #[rstest]
+    fn test_add_new_js(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let mut args = NewArgs {
+            description: Some(String::from("The \"web\" app\\frontend")),
+            project: true,
+            prog_type: Some(String::from("js")),
+            ..new_args("web")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("web");
+
+        let package: serde_json::Value = serde_json::from_str(&fs::read_to_string(path.join("package.json")).unwrap()).unwrap();
+        assert_eq!("web", package["name"]);
+        assert_eq!("The \"web\" app\\frontend", package["description"]);
+        assert!(path.join("index.js").exists());
+        assert!(!path.join("tsconfig.json").exists());
+        assert!(fs::read_to_string(path.join(".gitignore")).unwrap().contains("node_modules/"));
+
+        args.name = Some(String::from("web-ts"));
+        args.prog_type = Some(String::from("ts"));
+        boil.add_new(args).unwrap();
+        path.set_file_name("web-ts");
+
+        assert!(path.join("tsconfig.json").exists());
+        assert!(path.join("src/index.ts").exists());
+        let package: serde_json::Value = serde_json::from_str(&fs::read_to_string(path.join("package.json")).unwrap()).unwrap();
+        assert_eq!("The \"web\" app\\frontend", package["description"]);
+        assert_eq!(ProgType::from("typescript"), boil.config.get(String::from("web-ts")).unwrap().prog_type);
+    }
+
+    #[rstest]
+    fn test_languages(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+
+        let mut cfg = fs::read_to_string(&path).unwrap();
+        cfg.push_str(r#"
+[languages.zig]
+aliases = ["ziglang"]
+ext = "zig"
+script = "// {{name}}\n"
+scaffold = [
+    { path = "build.zig", contents = "// build {{name}}" },
+    { path = "src/main.zig", contents = "", when = "!lib" },
+]
+
+[languages.python]
+interpreter = "/usr/bin/python3.12"
+"#);
+        fs::write(&path, cfg).unwrap();
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        boil.config.defaults.bin_path = config.path().to_path_buf();
+
+        let mut args = NewArgs {
+            prog_type: Some(String::from("ZigLang")),
+            ..new_args("hello")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("hello.zig");
+        assert_eq!("// hello\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(ProgType::from("zig"), boil.config.get(String::from("hello")).unwrap().prog_type);
+
+        args.name = Some(String::from("zproj"));
+        args.project = true;
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("zproj");
+        assert_eq!("// build zproj", fs::read_to_string(path.join("build.zig")).unwrap());
+        assert!(path.join("src/main.zig").exists());
+
+        // Overriding a single field keeps the rest of the built-in language
+        args.name = Some(String::from("snake"));
+        args.project = false;
+        args.prog_type = Some(String::from("py"));
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("snake.py");
+        assert_eq!("#!/usr/bin/python3.12\n", fs::read_to_string(&path).unwrap());
+
+        // Types are listed by the name of their language
+        let languages = boil.config.languages();
+        assert_eq!("JavaScript", languages.display_name(&ProgType::from("javascript")));
+        assert_eq!("Python", languages.display_name(&ProgType::from("python")));
+        assert_eq!("Zig", languages.display_name(&ProgType::from("zig")));
+        assert_eq!("Cobol", languages.display_name(&ProgType::from("cobol")));
+
+        // Unknown types are an error instead of defaulting to bash
+        args.name = Some(String::from("cobol"));
+        args.prog_type = Some(String::from("cobol"));
+        assert!(matches!(boil.add_new(args), Err(BoilError::UnknownType(_))));
+        assert!(!boil.config.exists("cobol"));
+    }
+
+    #[rstest]
+    fn test_add_new_git(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+
+        // Hooks run before the commit, giving each repository an identity of its own
+        // so the user's git config doesn't matter
+        let mut cfg = fs::read_to_string(&path).unwrap();
+        cfg.push_str(r#"
+[languages.go]
+hooks = ["git config user.name boil", "git config user.email boil@example.com"]
+"#);
+        fs::write(&path, cfg).unwrap();
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let mut args = NewArgs {
+            project: true,
+            prog_type: Some(String::from("go")),
+            commit: true,
+            ..new_args("repo")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("repo");
+
+        assert!(path.join(".git").is_dir());
+        assert!(path.join(".gitignore").exists());
+
+        let log = std::process::Command::new("git")
+            .args(["log", "--oneline"])
+            .current_dir(&path)
+            .output()
+            .unwrap();
+        assert!(String::from_utf8_lossy(&log.stdout).contains("Initial commit"));
+
+        // Config default with an opt-out
+        boil.config.defaults.git = true;
+        args.commit = false;
+        args.no_git = true;
+        args.name = Some(String::from("no-repo"));
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("no-repo");
+        assert!(!path.join(".git").exists());
+
+        args.no_git = false;
+        args.name = Some(String::from("default-repo"));
+        boil.add_new(args).unwrap();
+        path.set_file_name("default-repo");
+        assert!(path.join(".git").is_dir());
+    }
+
+    #[rstest]
+    fn test_add_new_license(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        boil.config.defaults.author = Some(String::from("Jane Doe"));
+
+        let args = NewArgs {
+            project: true,
+            license: Some(String::from("MIT")),
+            ..new_args("licensed")
+        };
+
+        boil.add_new(args).unwrap();
+        path.set_file_name("licensed");
+
+        let text = fs::read_to_string(path.join("LICENSE")).unwrap();
+        assert!(text.starts_with("MIT License"));
+        assert!(text.contains("Jane Doe"));
+        assert!(!text.contains("{{"));
+        assert_eq!(Some(String::from("MIT")), boil.config.get(String::from("licensed")).unwrap().license);
+    }
+
+    #[rstest]
+    fn test_add_new_hooks(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+
+        let mut cfg = fs::read_to_string(&path).unwrap();
+        cfg.push_str(r#"
+[languages.bash]
+hooks = ["echo \"$BOIL_NAME:$BOIL_TYPE:$BOIL_DESCRIPTION\" > hook.txt"]
+"#);
+        fs::write(&path, cfg).unwrap();
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        let templates = config.path().join("templates");
+        fs::create_dir_all(templates.join("hooked")).unwrap();
+        let manifest = r#"hooks = ["touch {{name}}.done", "printf %s {{description}} > desc.txt", "exit 3"]"#;
+        fs::write(templates.join("hooked/boil.toml"), manifest).unwrap();
+        boil.config.defaults.template_path = templates.to_owned();
+
+        let mut args = NewArgs {
+            description: Some(String::from("it's; touch pwned")),
+            project: true,
+            template: Some(String::from("hooked")),
+            ..new_args("hooked")
+        };
+
+        // A failing hook is only reported by default
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("hooked");
+
+        assert_eq!("hooked:bash:it's; touch pwned\n", fs::read_to_string(path.join("hook.txt")).unwrap());
+        assert!(path.join("hooked.done").exists());
+
+        // Values are quoted rather than run
+        assert_eq!("it's; touch pwned", fs::read_to_string(path.join("desc.txt")).unwrap());
+        assert!(!path.join("pwned").exists());
+        assert!(!path.join("boil.toml").exists());
+        assert!(boil.config.exists("hooked"));
+
+        // Or rolls back the new project
+        boil.config.defaults.hook_rollback = true;
+        args.name = Some(String::from("rolled-back"));
+        assert!(matches!(boil.add_new(args.clone()), Err(BoilError::HookFailed(_, _))));
+        path.set_file_name("rolled-back");
+        assert!(!path.exists());
+        assert!(!boil.config.exists("rolled-back"));
+
+        fs::write(templates.join("hooked/boil.toml"), r#"hooks = ["kill -9 $$"]"#).unwrap();
+        args.name = Some(String::from("killed"));
+        assert!(matches!(boil.add_new(args), Err(BoilError::HookKilled(_, s)) if s == "9"));
+    }
+
+    #[rstest]
+    fn test_add_new_link(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        let links = config.path().join("links");
+        boil.config.defaults.bin_path = config.path().to_path_buf();
+        boil.config.defaults.link_path = links.to_owned();
+
+        let mut args = NewArgs {
+            prog_type: Some(String::from("python")),
+            link: true,
+            strip_ext: true,
+            ..new_args("script")
+        };
+
+        boil.add_new(args.clone()).unwrap();
+        path.set_file_name("script.py");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(0o111, fs::metadata(&path).unwrap().permissions().mode() & 0o111);
+        }
+
+        let link = links.join("script");
+        assert_eq!(path, fs::read_link(&link).unwrap());
+        assert_eq!(Some(link.to_owned()), boil.config.get(String::from("script")).unwrap().link);
+
+        // A taken link name is an error
+        fs::write(links.join("taken"), "").unwrap();
+        args.name = Some(String::from("taken"));
+        assert!(boil.add_new(args.clone()).is_err());
+        path.set_file_name("taken.py");
+        assert!(!path.exists());
+        path.set_file_name("script.py");
+
+        boil.remove(RemoveArgs { name: String::from("script"), force: true }).unwrap();
+        assert!(link.symlink_metadata().is_err());
+        assert!(path.exists());
+
+        // A link pointing somewhere else by now is left alone
+        #[cfg(unix)]
+        {
+            args.name = Some(String::from("relinked"));
+            boil.add_new(args.clone()).unwrap();
+            let relinked = links.join("relinked");
+            fs::remove_file(&relinked).unwrap();
+            std::os::unix::fs::symlink(&path, &relinked).unwrap();
+            boil.remove(RemoveArgs { name: String::from("relinked"), force: true }).unwrap();
+            assert_eq!(path, fs::read_link(&relinked).unwrap());
+        }
+
+        // Temp scripts aren't linked, even when linking is on by default
+        boil.config.defaults.link = true;
+        let temp = NewArgs { temp: true, link: false, strip_ext: false, ..new_args("boil-link-test.py") };
+        boil.add_new(temp).unwrap();
+        let temp_path = boil.config.temp.path.to_owned();
+        fs::remove_file(&temp_path).unwrap();
+        assert_eq!(None, boil.config.temp.link);
+        assert!(links.join("boil-link-test.py").symlink_metadata().is_err());
+    }
+
+    #[rstest]
+    fn test_add_new_rollback(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+        let bin = config.path().join("new").join("bin");
+        boil.config.defaults.bin_path = bin.to_owned();
+        // Linking fails after the script and its directories were created
+        fs::write(config.path().join("links"), "").unwrap();
+        boil.config.defaults.link_path = config.path().join("links").join("bin");
+
+        let mut args = NewArgs {
+            prog_type: Some(String::from("python")),
+            link: true,
+            ..new_args("script")
+        };
+
+        assert!(boil.add_new(args.clone()).is_err());
+        assert!(!config.path().join("new").exists());
+        assert!(!boil.config.exists("script"));
+
+        // A project whose hook fails is removed along with everything in it
+        boil.config.defaults.proj_path = config.path().join("projects");
+        boil.config.defaults.hook_rollback = true;
+        let templates = config.path().join("templates");
+        fs::create_dir_all(templates.join("failing/src")).unwrap();
+        fs::write(templates.join("failing/boil.toml"), "hooks = [\"touch extra && exit 1\"]").unwrap();
+        boil.config.defaults.template_path = templates;
+        args.project = true;
+        args.link = false;
+        args.template = Some(String::from("failing"));
+
+        assert!(boil.add_new(args.clone()).is_err());
+        assert!(!config.path().join("projects").exists());
+        assert!(!boil.config.exists("script"));
+
+        // Pre-existing parent directories are left alone
+        fs::create_dir(config.path().join("projects")).unwrap();
+        assert!(boil.add_new(args).is_err());
+        assert!(config.path().join("projects").exists());
+        assert!(!config.path().join("projects").join("script").exists());
+    }
+
+    #[rstest]
+    fn test_open(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+
+        let cfg_path = path.to_owned();
+        let opened = config.path().join("opened.txt");
+        let snapshot = config.path().join("snapshot.toml");
+        let editor = format!("cp {} {}; printf '%s\\\\n' >> {}", path.to_string_lossy(), snapshot.to_string_lossy(), opened.to_string_lossy());
+        let mut cfg = fs::read_to_string(&path).unwrap();
+        cfg.push_str(&format!("\n[languages.python]\neditor = \"{}\"\n", editor));
+        fs::write(&path, cfg).unwrap();
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        boil.open(OpenArgs { name: String::from("test0") }).unwrap();
+        path.set_file_name("test0");
+        assert_eq!(format!("{}\n", path.to_string_lossy()), fs::read_to_string(&opened).unwrap());
+
+        // The lock isn't held while the editor is open
+        let timeout = std::time::Duration::from_millis(100);
+        ConfigLock::acquire(&cfg_path, timeout).unwrap();
+        drop(boil);
+        let mut boil = Boil::from(Some(cfg_path.to_owned())).unwrap();
+        boil.config.defaults.bin_path = config.path().to_path_buf();
+
+        let args = NewArgs {
+            prog_type: Some(String::from("python")),
+            open: true,
+            ..new_args("edited")
+        };
+        boil.add_new(args).unwrap();
+        path.set_file_name("edited.py");
+        assert!(fs::read_to_string(&opened).unwrap().ends_with(&format!("{}\n", path.to_string_lossy())));
+
+        // The new entry was saved before the editor was opened
+        assert!(fs::read_to_string(&snapshot).unwrap().contains("[programs.edited]"));
+        ConfigLock::acquire(&cfg_path, timeout).unwrap();
+        boil.write().unwrap();
+        drop(boil);
+
+        let mut boil = Boil::from_shared(Some(cfg_path.to_owned())).unwrap();
+        assert!(matches!(boil.open(OpenArgs { name: String::from("missing") }), Err(BoilError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let v1 = r#"
+[programs.old]
+name = "old"
+project = false
+path = "/tmp/old.js"
+type = "JavaScript"
+tags = ["util"]
+
+[defaults]
+proj_path = "/tmp"
+bin_path = "/tmp"
+
+[temp]
+name = ""
+project = false
+path = ""
+type = "Bash"
+"#;
+        fs::write(&path, v1).unwrap();
+
+        let report = Config::check(&path).unwrap();
+        assert!(report.is_needed());
+        assert_eq!((1, migrate::CONFIG_VERSION), (report.from, report.to));
+        assert!(report.changes.contains(&String::from("programs.old.type: 'JavaScript' -> 'javascript'")));
+        assert!(report.changes.contains(&String::from("temp.type: 'Bash' -> 'bash'")));
+
+        // Checking leaves the config alone
+        Boil::migrate(MigrateArgs { check: true }, Some(path.to_owned())).unwrap();
+        assert_eq!(v1, fs::read_to_string(&path).unwrap());
+
+        // Readers save the upgrade holding the config alone, then share it again
+        let reader = Boil::from_shared(Some(path.to_owned())).unwrap();
+        assert!(reader.lock.as_ref().unwrap().shared);
+        assert_eq!(migrate::CONFIG_VERSION, reader.config.version);
+        let other = Boil::from_shared(Some(path.to_owned())).unwrap();
+        assert_eq!(migrate::CONFIG_VERSION, Config::check(&path).unwrap().from);
+        assert!(!dir.path().join("config.toml.v1.bak.1").exists());
+        drop((reader, other));
+        fs::write(&path, v1).unwrap();
+        fs::remove_file(dir.path().join("config.toml.v1.bak")).unwrap();
+
+        let boil = Boil::from(Some(path.to_owned())).unwrap();
+        assert_eq!(migrate::CONFIG_VERSION, boil.config.version);
+        assert_eq!(ProgType::from("javascript"), boil.config.get(String::from("old")).unwrap().prog_type);
+        assert_eq!(v1, fs::read_to_string(dir.path().join("config.toml.v1.bak")).unwrap());
+        assert!(fs::read_to_string(&path).unwrap().starts_with(&format!("version = {}", migrate::CONFIG_VERSION)));
+        drop(boil);
+
+        // Already up to date, so nothing else is backed up
+        assert!(!Config::check(&path).unwrap().is_needed());
+        Boil::migrate(MigrateArgs { check: false }, Some(path.to_owned())).unwrap();
+        assert!(!dir.path().join("config.toml.v1.bak.1").exists());
+
+        fs::write(&path, "version = 999\n").unwrap();
+        assert!(matches!(Boil::from(Some(path.to_owned())), Err(BoilError::ConfigVersion(999, _))));
+    }
+
+    #[rstest]
+    fn test_restore(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let original = fs::read_to_string(&path).unwrap();
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        // Nothing changed, nothing to back up
+        boil.write().unwrap();
+        assert!(backup::list(&path).unwrap().is_empty());
+        boil.config.defaults.backups = 3;
+
+        for i in 0..5 {
+            boil.config.remove(format!("test{}", i % 3)).ok();
+            boil.config.defaults.author = Some(format!("author {}", i));
+            boil.write().unwrap();
+        }
+        drop(boil);
+
+        // Only the newest are kept and the temp file is gone
+        let backups = backup::list(&path).unwrap();
+        assert_eq!(3, backups.len());
+        assert!(backups[0].millis > backups[1].millis);
+        assert!(!config.path().join(".config.toml.tmp").exists());
+        Boil::restore(RestoreArgs { list: true, backup: None }, Some(path.to_owned())).unwrap();
+
+        // Restored exactly as it was backed up, comments and all
+        let newest = format!("# kept by hand\n{}", fs::read_to_string(&backups[0].path).unwrap());
+        fs::write(&backups[0].path, &newest).unwrap();
+        let current = fs::read_to_string(&path).unwrap();
+        Boil::restore(RestoreArgs { list: false, backup: Some(1) }, Some(path.to_owned())).unwrap();
+        assert_eq!(newest, fs::read_to_string(&path).unwrap());
+        assert_ne!(original, newest);
+
+        // The replaced config became the newest backup
+        let backups = backup::list(&path).unwrap();
+        assert_eq!(3, backups.len());
+        assert_eq!(current, fs::read_to_string(&backups[0].path).unwrap());
+
+        for n in [0, 4] {
+            let res = Boil::restore(RestoreArgs { list: false, backup: Some(n) }, Some(path.to_owned()));
+            assert!(matches!(res, Err(BoilError::BackupNotFound(_))));
+        }
+
+        // A symlinked config stays a symlink and keeps its permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{symlink, PermissionsExt};
+
+            let link = config.path().join("linked.toml");
+            symlink(&path, &link).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+            let (linked, _) = Config::parse(&fs::read_to_string(&link).unwrap(), Format::Toml).unwrap();
+            linked.write(&link).unwrap();
+            assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+            assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        }
+    }
+
+    #[rstest]
+    fn test_lock(config: TempDir) {
+        let mut path = PathBuf::from(config.path());
+        path.push("config.toml");
+        let timeout = std::time::Duration::from_millis(100);
+        let mut boil = Boil::from(Some(path.to_owned())).unwrap();
+
+        // Another command has to wait for the first one
+        let res = ConfigLock::acquire(&path, timeout);
+        assert!(matches!(res, Err(BoilError::Locked(_, _))));
+
+        // Writing twice is fine, but not over someone else's edit
+        boil.config.defaults.author = Some(String::from("first"));
+        boil.write().unwrap();
+        boil.config.defaults.author = Some(String::from("second"));
+        boil.write().unwrap();
+
+        let edited = format!("{}\n# edited by hand\n", fs::read_to_string(&path).unwrap());
+        fs::write(&path, &edited).unwrap();
+        assert!(matches!(boil.write(), Err(BoilError::ConfigModified(_))));
+        assert_eq!(edited, fs::read_to_string(&path).unwrap());
+
+        drop(boil);
+        let lock = ConfigLock::acquire(&path, timeout).unwrap();
+        assert_eq!(config.path().join("config.toml.lock"), lock.path);
+        drop(lock);
+
+        // Commands only reading the config can run side by side, but not next to one changing it
+        let reader = Boil::from_shared(Some(path.to_owned())).unwrap();
+        let other = Boil::from_shared(Some(path.to_owned())).unwrap();
+        assert!(matches!(ConfigLock::acquire(&path, timeout), Err(BoilError::Locked(_, _))));
+        drop((reader, other));
+
+        let _writer = Boil::from(Some(path.to_owned())).unwrap();
+        assert!(matches!(ConfigLock::acquire_shared(&path, timeout), Err(BoilError::Locked(_, _))));
+    }
+
+    #[rstest]
+    fn test_config_convert(config: TempDir) {
+        let path = config.path().join("config.toml");
+        let convert = |to: &str, output: Option<PathBuf>, path: &PathBuf| {
+            let command = ConfigCommands::Convert { to: Format::from_str(to).unwrap(), output };
+            Boil::config(ConfigArgs { command }, Some(path.to_owned()))
+        };
+
+        // A copy for other tools, the config itself is left alone
+        let copy = config.path().join("catalog.json");
+        convert("json", Some(copy.to_owned()), &path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&copy).unwrap()).unwrap();
+        assert_eq!("Fun program", json["programs"]["test0"]["description"]);
+        assert!(json["programs"]["test2"].get("description").is_none());
+        assert!(matches!(convert("json", Some(copy), &path), Err(BoilError::PathExists(_))));
+        assert!(path.is_file());
+
+        convert("yaml", None, &path).unwrap();
+        let yaml = config.path().join("config.yaml");
+        assert!(!path.exists());
+        assert!(config.path().join("config.toml.bak").is_file());
+
+        // A path given for the old config still finds it
+        assert_eq!(yaml, Boil::profile_config(Some(path.to_owned()), Some("default")).unwrap());
+
+        let mut boil = Boil::from(Some(yaml.to_owned())).unwrap();
+        assert_eq!(3, boil.config.len());
+        assert_eq!(Some(String::from("Utility program")), boil.config.get(String::from("test1")).unwrap().description);
+        boil.config.remove(String::from("test0")).unwrap();
+        boil.write().unwrap();
+        drop(boil);
+        assert!(!fs::read_to_string(&yaml).unwrap().contains("test0"));
+
+        // Backups of a YAML config are YAML as well
+        Boil::restore(RestoreArgs { list: false, backup: Some(1) }, Some(yaml.to_owned())).unwrap();
+        assert_eq!(3, Config::from(&yaml).unwrap().len());
+
+        convert("toml", None, &yaml).unwrap();
+        assert_eq!(3, Config::from(&path).unwrap().len());
+        assert!(fs::read_to_string(&path).unwrap().starts_with("version = "));
+        assert!(matches!(convert("toml", None, &path), Ok(())));
+        assert!(!config.path().join("config.toml.bak.1").exists());
+
+        // Nulls written by other tools or by hand are the same as leaving the field out
+        let json = r#"{"programs": {"x": {"name": "x", "project": false, "path": "/x", "type": "bash", "description": null, "tags": ["a", null]}}, "temp": null}"#;
+        let yaml = "programs:\n  x:\n    name: x\n    project: false\n    path: /x\n    type: bash\n    description:\n";
+
+        for (content, format) in [(json, Format::Json), (yaml, Format::Yaml)] {
+            let (parsed, _) = Config::parse(content, format).unwrap();
+            let program = parsed.get(String::from("x")).unwrap();
+            assert_eq!(None, program.description);
+        }
+        let (parsed, _) = Config::parse(json, Format::Json).unwrap();
+        assert_eq!(Some(vec![String::from("a")]), parsed.get(String::from("x")).unwrap().tags);
+
+        // A known extension decides the format, BOIL_CONFIG_FORMAT only applies to other files
+        assert_eq!(Format::Toml, Format::of(&path).unwrap());
+        assert_eq!(Format::Yaml, Format::of(Path::new("catalog.yml")).unwrap());
+
+        // Single entries, ie in a dry run, are shown in the same format
+        let program = parsed.get(String::from("x")).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(&Format::Json.dump(&BTreeMap::from([("temp", program)])).unwrap()).unwrap();
+        assert_eq!("a", entry["temp"]["tags"][0]);
+        assert!(entry["temp"].get("description").is_none());
+        assert!(Format::Toml.dump(&BTreeMap::from([("temp", program)])).unwrap().starts_with("[temp]\nname = "));
+    }
+
+    #[rstest]
+    fn test_profile(config: TempDir) {
+        let base = config.path().join("config.toml");
+        let work = config.path().join("profiles/work/config.toml");
+        let run = |command: ProfileCommands| Boil::profile(ProfileArgs { command }, Some(base.to_owned()));
+        let create = |name: &str, path: Option<PathBuf>| {
+            run(ProfileCommands::Create { path, switch: false, name: name.to_owned() })
+        };
+
+        assert_eq!(vec!["default"], profile::list(&base).unwrap());
+        assert_eq!(base, Boil::profile_config(Some(base.to_owned()), None).unwrap());
+        let res = Boil::profile_config(Some(base.to_owned()), Some("work"));
+        assert!(matches!(res, Err(BoilError::ProfileNotFound(_))));
+
+        create("work", Some(PathBuf::from("/tmp/work"))).unwrap();
+        assert_eq!(work, Boil::profile_config(Some(base.to_owned()), Some("work")).unwrap());
+        assert!(matches!(create("work", None), Err(BoilError::ProfileExists(_))));
+        assert!(matches!(create("default", None), Err(BoilError::ProfileExists(_))));
+        assert!(matches!(create("../work", None), Err(BoilError::InvalidProfile(_))));
+        assert!(matches!(Boil::profile_config(Some(base.to_owned()), Some("..")), Err(BoilError::ProfileNotFound(_))));
+
+        // Each profile has its own entries and paths
+        let mut boil = Boil::from(Some(work.to_owned())).unwrap();
+        assert_eq!(0, boil.config.len());
+        assert_eq!(PathBuf::from("/tmp/work"), boil.config.defaults.proj_path);
+        boil.config.insert(String::from("deploy"), Program { name: String::from("deploy"), ..Default::default() });
+        boil.write().unwrap();
+        drop(boil);
+        assert_eq!(3, Config::from(&base).unwrap().len());
+
+        // The profile in use is remembered
+        run(ProfileCommands::Use { name: String::from("work") }).unwrap();
+        assert_eq!(work, Boil::profile_config(Some(base.to_owned()), None).unwrap());
+        assert_eq!(base, Boil::profile_config(Some(base.to_owned()), Some("default")).unwrap());
+        assert_eq!(vec!["default", "work"], profile::list(&base).unwrap());
+        run(ProfileCommands::List).unwrap();
+
+        let res = run(ProfileCommands::Use { name: String::from("home") });
+        assert!(matches!(res, Err(BoilError::ProfileNotFound(_))));
+        let res = run(ProfileCommands::Remove { force: true, name: String::from("default") });
+        assert!(matches!(res, Err(BoilError::InvalidProfile(_))));
+
+        // Removing the profile in use goes back to the default
+        run(ProfileCommands::Remove { force: true, name: String::from("work") }).unwrap();
+        assert!(!config.path().join("profiles/work").exists());
+        assert_eq!(base, Boil::profile_config(Some(base.to_owned()), None).unwrap());
+        assert_eq!(3, Config::from(&base).unwrap().len());
+    }
+
     #[rstest]
     fn test_edit(config: TempDir) {
         let mut path = PathBuf::from(config.path());
@@ -472,7 +2506,8 @@ mod config_tests {
                 description: Some(String::from("Not fun program")),
                 tags: Some(vec!["test".to_string()]),
                 rm_tags: Some(vec!["util".to_string()]),
-                prog_type: None
+                prog_type: None,
+                license: None
             }
         };
 
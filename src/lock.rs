@@ -0,0 +1,77 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{BoilError, BoilResult};
+
+/// Seconds to wait for another boil command to release the config when
+/// `BOIL_LOCK_TIMEOUT` isn't set
+pub const DEFAULT_LOCK_TIMEOUT: u64 = 10;
+
+/// How often a held lock is retried while waiting for it
+const RETRY: Duration = Duration::from_millis(50);
+
+/// An advisory lock on the config, held from loading it until the command is done so
+/// concurrent boil commands can't overwrite each other's changes. Commands only reading
+/// the config share the lock, while one changing it holds it alone.
+/// The lock is taken on a separate `<file>.lock` since writing the config replaces
+/// the file itself. It is released when dropped, or when the process exits
+#[derive(Debug)]
+pub struct ConfigLock {
+    file: File,
+    pub path: PathBuf,
+    pub shared: bool,
+}
+
+impl ConfigLock {
+    /// Locks the config at `path` to change it, waiting up to `timeout` for any other
+    /// command holding it
+    pub fn acquire(path: &Path, timeout: Duration) -> BoilResult<Self> {
+        Self::lock(path, timeout, false)
+    }
+
+    /// Locks the config at `path` to read it, waiting up to `timeout` for a command changing it
+    pub fn acquire_shared(path: &Path, timeout: Duration) -> BoilResult<Self> {
+        Self::lock(path, timeout, true)
+    }
+
+    fn lock(path: &Path, timeout: Duration, shared: bool) -> BoilResult<Self> {
+        let lock_path = lock_path(path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let res = match shared {
+                true => file.try_lock_shared(),
+                false => file.try_lock(),
+            };
+
+            match res {
+                Ok(()) => return Ok(Self { file, path: lock_path, shared }),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => thread::sleep(RETRY),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(BoilError::Locked(path.to_path_buf(), timeout.as_secs_f32()))
+                },
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// File locked in place of the config at `path`
+pub fn lock_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.lock", name))
+}
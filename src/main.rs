@@ -1,6 +1,7 @@
 #![allow(unused)]
 use std::process::ExitCode;
 use std::env;
+use std::io::{self, IsTerminal};
 use std::path::{PathBuf, Path};
 
 use clap::Parser;
@@ -19,30 +20,53 @@ fn main() -> ExitCode {
     }
 
 
-    if let Commands::Init(a) = args.command {
-        match Boil::init(a, args.cfg_path) {
-            Ok(_) => return ExitCode::SUCCESS,
-            Err(e) => {
-                eprintln!("boil error: {e}");
-                return ExitCode::FAILURE
-            }
+    let command = match args.command {
+        Commands::Profile(a) => return exit_code(Boil::profile(a, args.cfg_path)),
+        command => command
+    };
+
+    let cfg_path = match Boil::profile_config(args.cfg_path, args.profile.as_deref()) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            eprintln!("boil error: {e}");
+            return ExitCode::FAILURE
         }
     };
 
+    // Commands working on the config file itself, before (or instead of) loading it
+    let command = match command {
+        Commands::Init(a) => return exit_code(Boil::init(a, cfg_path)),
+        Commands::Migrate(a) => return exit_code(Boil::migrate(a, cfg_path)),
+        Commands::Restore(a) => return exit_code(Boil::restore(a, cfg_path)),
+        Commands::Config(a) => return exit_code(Boil::config(a, cfg_path)),
+        command => command
+    };
+
+    let modifies_config = command.modifies_config();
+
+    let loaded = match modifies_config {
+        true => Boil::from(cfg_path),
+        false => Boil::from_shared(cfg_path),
+    };
 
-    let mut boil = match Boil::from(args.cfg_path) {
+    let mut boil = match loaded {
         Ok(b) => b,
         Err(e) => {
             eprintln!("boil error: {e}");
             return ExitCode::FAILURE
         }
     };
+    boil.interactive = io::stdin().is_terminal();
 
-    if let Err(e) = boil.run(args.command){
+    if let Err(e) = boil.run(command){
         eprintln!("boil error: {e}");
         return ExitCode::FAILURE
     }
 
+    if !modifies_config {
+        return ExitCode::SUCCESS
+    }
+
     if let Err(e) = boil.write() {
         eprintln!("boil error: {e}");
         return ExitCode::FAILURE
@@ -51,6 +75,16 @@ fn main() -> ExitCode {
     ExitCode::SUCCESS
 }
 
+fn exit_code(res: BoilResult<()>) -> ExitCode {
+    match res {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("boil error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,7 +101,7 @@ mod tests {
     #[test]
     fn test_create_config() {
         // set_dev_env_vars();
-        let boil = Boil::from(None).unwrap();
+        let mut boil = Boil::from(None).unwrap();
         boil.write();
 
         assert_eq!(boil.cfg_path, [dirs::home_dir().unwrap(), Path::new("dev/rs-boil/tests/dev/sample-config.toml").to_path_buf()].iter().collect::<PathBuf>());
@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::backup;
+use crate::error::{BoilError, BoilResult};
+
+/// Version of the config written by this version of boil. Configs written
+/// before the `version` key was added are version 1
+pub const CONFIG_VERSION: u32 = 2;
+
+/// A step upgrading a config from version `from` to `from + 1`. It returns a
+/// description of each change made
+struct Migration {
+    from: u32,
+    apply: fn(&mut Table) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { from: 1, apply: lowercase_types },
+];
+
+/// What migrating a config did, or would do
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub from: u32,
+    pub to: u32,
+    pub changes: Vec<String>,
+}
+
+impl Report {
+    pub fn is_needed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// Version of a raw config
+pub fn version(table: &Table) -> u32 {
+    table.get("version")
+        .and_then(|v| v.as_integer())
+        .map_or(1, |v| v as u32)
+}
+
+/// Upgrades the raw config `table` to `CONFIG_VERSION` in place
+pub fn migrate(table: &mut Table) -> BoilResult<Report> {
+    let from = version(table);
+
+    if from > CONFIG_VERSION {
+        return Err(BoilError::ConfigVersion(from, CONFIG_VERSION))
+    }
+
+    let mut changes = vec![];
+
+    for step in MIGRATIONS.iter().filter(|m| m.from >= from) {
+        changes.extend((step.apply)(table));
+    }
+
+    if from != CONFIG_VERSION {
+        changes.push(format!("version: {} -> {}", from, CONFIG_VERSION));
+    }
+    table.insert(String::from("version"), Value::Integer(CONFIG_VERSION.into()));
+
+    Ok(Report { from, to: CONFIG_VERSION, changes })
+}
+
+/// Saves `content`, the config at `path` before migrating from `version`, next to it
+/// as `<file>.v<version>.bak`. Existing backups are never overwritten
+pub fn backup(path: &Path, content: &str, version: u32) -> BoilResult<PathBuf> {
+    let backup = backup::unused_name(path, &format!("v{}.bak", version))?;
+    fs::write(&backup, content)?;
+
+    Ok(backup)
+}
+
+/// Version 1 stored types as enum variants, ie `Python` or `JavaScript`, while
+/// types are now the ids of the language registry
+fn lowercase_types(table: &mut Table) -> Vec<String> {
+    let mut changes = vec![];
+
+    let mut lowercase = |key: String, program: &mut Value| {
+        if let Some(Value::String(t)) = program.get_mut("type") {
+            let id = t.to_lowercase();
+            if *t != id {
+                changes.push(format!("{}.type: '{}' -> '{}'", key, t, id));
+                *t = id;
+            }
+        }
+    };
+
+    if let Some(Value::Table(programs)) = table.get_mut("programs") {
+        for (name, program) in programs.iter_mut() {
+            lowercase(format!("programs.{}", name), program);
+        }
+    }
+
+    if let Some(temp) = table.get_mut("temp") {
+        lowercase(String::from("temp"), temp);
+    }
+
+    changes
+}
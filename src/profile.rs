@@ -0,0 +1,142 @@
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::defaults::find_config;
+use crate::error::{BoilError, BoilResult};
+
+/// The profile of the config boil has always used, kept where it always was
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Profiles are relative to `base`, the config of the default profile. Every other
+// profile is a directory of its own under `profiles/` next to it, holding its config
+// along with that config's backups and lock. The profile used when none is given is
+// remembered in `profile` next to `base`
+
+fn profiles_dir(base: &Path) -> PathBuf {
+    base.with_file_name("profiles")
+}
+
+fn remembered_file(base: &Path) -> PathBuf {
+    base.with_file_name("profile")
+}
+
+/// Path of the config of the profile `name`, which may not exist yet
+pub fn config_path(base: &Path, name: &str) -> PathBuf {
+    match name {
+        DEFAULT_PROFILE => find_config(base.to_path_buf()),
+        _ => find_config(profiles_dir(base).join(name).join("config.toml")),
+    }
+}
+
+pub fn exists(base: &Path, name: &str) -> bool {
+    name == DEFAULT_PROFILE || (valid_name(name).is_ok() && profiles_dir(base).join(name).is_dir())
+}
+
+/// Path of the config of `name` - or the active profile if not given - which has to exist
+pub fn find(base: &Path, name: Option<&str>) -> BoilResult<PathBuf> {
+    let name = match name {
+        Some(n) => n.to_owned(),
+        None => active(base)?,
+    };
+
+    if !exists(base, &name) {
+        return Err(BoilError::ProfileNotFound(name))
+    }
+
+    Ok(config_path(base, &name))
+}
+
+/// Profile used when none is given - `BOIL_PROFILE` if set, otherwise the one
+/// remembered by `boil profile use`
+pub fn active(base: &Path) -> BoilResult<String> {
+    match env::var("BOIL_PROFILE") {
+        Ok(p) => Ok(p),
+        Err(_) => remembered(base),
+    }
+}
+
+/// Profile remembered by `boil profile use`, the default if none is
+fn remembered(base: &Path) -> BoilResult<String> {
+    match fs::read_to_string(remembered_file(base)) {
+        Ok(name) if !name.trim().is_empty() => Ok(name.trim().to_owned()),
+        Ok(_) => Ok(String::from(DEFAULT_PROFILE)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::from(DEFAULT_PROFILE)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Makes `name` the profile used when none is given
+pub fn remember(base: &Path, name: &str) -> BoilResult<()> {
+    let file = remembered_file(base);
+
+    if name == DEFAULT_PROFILE {
+        if file.try_exists()? {
+            fs::remove_file(file)?;
+        }
+        return Ok(())
+    }
+
+    fs::write(file, format!("{}\n", name))?;
+    Ok(())
+}
+
+/// Names of all profiles, the default first
+pub fn list(base: &Path) -> BoilResult<Vec<String>> {
+    let mut names = vec![];
+    let dir = profiles_dir(base);
+
+    if dir.is_dir() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+
+    names.sort();
+    names.insert(0, String::from(DEFAULT_PROFILE));
+
+    Ok(names)
+}
+
+/// Creates the directory of the new profile `name`, returning the path of its config
+pub fn create(base: &Path, name: &str) -> BoilResult<PathBuf> {
+    valid_name(name)?;
+
+    if exists(base, name) {
+        return Err(BoilError::ProfileExists(name.to_owned()))
+    }
+
+    fs::create_dir_all(profiles_dir(base).join(name))?;
+    Ok(config_path(base, name))
+}
+
+/// Deletes the profile `name` along with its config and backups
+pub fn remove(base: &Path, name: &str) -> BoilResult<()> {
+    if name == DEFAULT_PROFILE {
+        return Err(BoilError::InvalidProfile(name.to_owned()))
+    }
+    if !exists(base, name) {
+        return Err(BoilError::ProfileNotFound(name.to_owned()))
+    }
+
+    fs::remove_dir_all(profiles_dir(base).join(name))?;
+
+    if remembered(base)? == name {
+        remember(base, DEFAULT_PROFILE)?;
+    }
+
+    Ok(())
+}
+
+/// Checks `name` is a single path component so profiles stay inside the profiles directory
+fn valid_name(name: &str) -> BoilResult<&str> {
+    let mut components = Path::new(name).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(name),
+        _ => Err(BoilError::InvalidProfile(name.to_owned()))
+    }
+}
@@ -0,0 +1,35 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{BoilError, BoilResult};
+use super::Language;
+
+/// Editor for entries of `lang` - its own `editor` if set, otherwise `$VISUAL` or `$EDITOR`.
+/// Blank settings are passed over
+pub fn editor(lang: &Language) -> Option<String> {
+    let set = |e: &String| !e.trim().is_empty();
+
+    lang.editor.clone().filter(set)
+        .or_else(|| env::var("VISUAL").ok().filter(set))
+        .or_else(|| env::var("EDITOR").ok().filter(set))
+}
+
+/// Opens `path` in the editor for `lang` and waits for it to exit. The editor is run
+/// through `sh` so it may include arguments, ie `code --wait`
+pub fn open(path: &Path, lang: &Language) -> BoilResult<()> {
+    let editor = editor(lang).ok_or(BoilError::EditorMissing)?;
+
+    let status = Command::new("sh")
+        .args(["-c", &format!("{} \"$1\"", editor), "boil"])
+        .arg(path)
+        .status()
+        .map_err(|e| BoilError::Editor(editor.to_owned(), e.to_string()))?;
+
+    if !status.success() {
+        let code = status.code().map_or(String::from("signal"), |c| c.to_string());
+        return Err(BoilError::Editor(editor, format!("exited with code {}", code)))
+    }
+
+    Ok(())
+}
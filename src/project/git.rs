@@ -0,0 +1,40 @@
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{BoilError, BoilResult};
+
+/// Initializes a new repository at `path`
+pub fn init(path: &Path) -> BoilResult<()> {
+    git(path, &["init", "--quiet"])
+}
+
+/// Stages everything in the repository at `path` and commits it
+pub fn commit_all(path: &Path, msg: &str) -> BoilResult<()> {
+    git(path, &["add", "--all"])?;
+    git(path, &["commit", "--quiet", "-m", msg])
+}
+
+/// Runs git with `args` from the directory `path`
+pub fn git(path: &Path, args: &[&str]) -> BoilResult<()> {
+    git_output(path, args).map(|_| ())
+}
+
+/// Runs git with `args` from the directory `path`, returning its trimmed output
+pub fn git_output(path: &Path, args: &[&str]) -> BoilResult<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => BoilError::GitMissing,
+            _ => BoilError::IO(e)
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(BoilError::Git(args[0].to_owned(), stderr))
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeSet;
+
+use crate::error::{BoilError, BoilResult};
+
+/// Bundled `.gitignore` snippets for languages, editors and operating systems
+/// along with the other names they can be referred to by
+const SNIPPETS: &[(&str, &[&str], &str)] = &[
+    ("c", &["cpp", "c++"], include_str!("gitignores/c.in")),
+    ("go", &["golang"], include_str!("gitignores/go.in")),
+    ("java", &["kotlin", "gradle", "maven"], include_str!("gitignores/java.in")),
+    ("node", &["javascript", "js", "typescript", "ts"], include_str!("gitignores/node.in")),
+    ("python", &["py"], include_str!("gitignores/python.in")),
+    ("ruby", &["rb"], include_str!("gitignores/ruby.in")),
+    ("rust", &["rs"], include_str!("gitignores/rust.in")),
+    ("emacs", &[], include_str!("gitignores/emacs.in")),
+    ("jetbrains", &["idea", "intellij", "pycharm"], include_str!("gitignores/jetbrains.in")),
+    ("vim", &["neovim", "nvim"], include_str!("gitignores/vim.in")),
+    ("vscode", &["code"], include_str!("gitignores/vscode.in")),
+    ("linux", &[], include_str!("gitignores/linux.in")),
+    ("macos", &["osx", "mac", "darwin"], include_str!("gitignores/macos.in")),
+    ("windows", &["win"], include_str!("gitignores/windows.in")),
+];
+
+/// Looks up a snippet by name or alias, ignoring case. Returns the
+/// canonical name and the snippet
+pub fn find(name: &str) -> BoilResult<(&'static str, &'static str)> {
+    SNIPPETS
+        .iter()
+        .find(|(id, aliases, _)| {
+            id.eq_ignore_ascii_case(name) || aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+        })
+        .map(|(id, _, text)| (*id, *text))
+        .ok_or_else(|| BoilError::UnknownGitignore(name.to_owned(), names().join(", ")))
+}
+
+pub fn names() -> Vec<&'static str> {
+    SNIPPETS.iter().map(|(id, _, _)| *id).collect()
+}
+
+/// Joins the snippets `names` into a single `.gitignore`, each under a header
+/// with its name. Patterns already added by an earlier snippet are left out
+pub fn compose<S: AsRef<str>>(names: &[S]) -> BoilResult<String> {
+    let mut out = String::new();
+    let mut done: BTreeSet<&str> = BTreeSet::new();
+
+    for name in names.iter() {
+        let (id, text) = find(name.as_ref())?;
+
+        if !done.insert(id) {
+            continue
+        }
+
+        let mut section = String::new();
+
+        for line in text.lines() {
+            // Comments and blank lines belong to their snippet, patterns only count once
+            if is_pattern(line) && out.lines().any(|l| l.trim() == line.trim()) {
+                continue
+            }
+            section.push_str(line);
+            section.push('\n');
+        }
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("# --- {} ---\n", id));
+        out.push_str(section.trim_end());
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Appends the patterns of `new` missing from `old`. Blank lines only separate
+/// the two and aren't carried over
+pub fn merge(old: &str, new: &str) -> String {
+    let existing: BTreeSet<&str> = old.lines().map(|l| l.trim()).collect();
+
+    let added: Vec<&str> = new.lines()
+        .filter(|l| !l.trim().is_empty() && !existing.contains(l.trim()))
+        .collect();
+
+    let mut out = old.to_owned();
+
+    if added.is_empty() {
+        return out
+    }
+
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    if !out.trim().is_empty() {
+        out.push('\n');
+    }
+    for line in added {
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn is_pattern(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
@@ -0,0 +1,61 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::config::Program;
+use crate::error::{BoilError, BoilResult};
+use crate::project::vars::{shell_quote, Vars};
+
+/// Runs each hook with `sh -c` from the new project's directory (or the directory
+/// of a new script), stopping at the first one that fails.
+///
+/// The entry is exported to the hooks as `BOIL_PATH`, `BOIL_PROJECT` and every
+/// template variable as `BOIL_<NAME>`, ie `BOIL_NAME`, `BOIL_TYPE`. Placeholders
+/// in a hook are replaced by their value quoted as a single shell word
+pub fn run_hooks(hooks: &[String], program: &Program, vars: &Vars) -> BoilResult<()> {
+    let dir = if program.project {
+        program.path.as_path()
+    } else {
+        program.path.parent().unwrap_or(Path::new("."))
+    };
+
+    for hook in hooks.iter() {
+        let cmd = render_hook(hook, vars);
+
+        // Hook output goes to stderr so the new path stays the only thing on stdout
+        let status = Command::new("sh")
+            .args(["-c", &cmd])
+            .current_dir(dir)
+            .envs(vars.iter().map(|(k, v)| (format!("BOIL_{}", k.to_uppercase()), v)))
+            .env("BOIL_PATH", &program.path)
+            .env("BOIL_PROJECT", program.project.to_string())
+            .stdin(Stdio::null())
+            .stdout(io::stderr())
+            .status()?;
+
+        if !status.success() {
+            return Err(match status.code() {
+                Some(code) => BoilError::HookFailed(cmd, code.to_string()),
+                None => BoilError::HookKilled(cmd, signal(&status)),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+/// The command run for `hook`, values of its placeholders can't run as shell code
+pub fn render_hook(hook: &str, vars: &Vars) -> String {
+    vars.render_with(hook, shell_quote)
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal().map_or(String::from("unknown"), |s| s.to_string())
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> String {
+    String::from("unknown")
+}
@@ -0,0 +1,366 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ProgType;
+use crate::error::{BoilError, BoilResult};
+use crate::utils::capitalize;
+use super::gitignore;
+
+/// Everything boil needs to know to create scripts and projects of a given type.
+///
+/// Built-in languages can be extended or overridden field by field with a
+/// `[languages.<id>]` table in the config, ie
+///
+/// ```toml
+/// [languages.go]
+/// aliases = ["golang"]
+/// ext = "go"
+/// scaffold = [{ path = "main.go", contents = "package main\n" }]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Language {
+    /// Name shown by `boil list`, ie `JavaScript`. The capitalized id if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Other names accepted by `--type`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+
+    /// Extension given to new scripts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ext: Option<String>,
+
+    /// Interpreter used for the shebang of new scripts, ie `/usr/bin/env python3`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+
+    /// Boilerplate written to new scripts after the shebang
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+
+    /// Files generated for new projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scaffold: Option<Vec<ScaffoldFile>>,
+
+    /// Contents of the `.gitignore` written to new projects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitignore: Option<String>,
+
+    /// Commands run after a new script or project is created, ie `python -m venv .venv`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Vec<String>>,
+
+    /// Files whose presence marks a directory as a project of this language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markers: Option<Vec<String>>,
+
+    /// Command used by `boil open` instead of `$VISUAL`/`$EDITOR`, ie `code --wait`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+}
+
+/// A single file of a project scaffold. Both `path` and `contents` may use
+/// `{{var}}` placeholders
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScaffoldFile {
+    /// Path relative to the project root
+    pub path: String,
+
+    #[serde(default)]
+    pub contents: String,
+
+    /// Only generate the file when the variable is set (`lib`) or unset (`!lib`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+/// The built-in languages merged with the ones defined in the config
+#[derive(Debug, Clone)]
+pub struct Languages(BTreeMap<String, Language>);
+
+impl Language {
+    /// Overrides any fields set in `other`. Aliases are added to the existing ones
+    pub fn merge(&mut self, other: &Language) {
+        for alias in other.aliases.iter() {
+            if !self.aliases.contains(alias) {
+                self.aliases.push(alias.to_owned());
+            }
+        }
+
+        if other.name.is_some() {
+            self.name = other.name.clone();
+        }
+        if other.ext.is_some() {
+            self.ext = other.ext.clone();
+        }
+        if other.interpreter.is_some() {
+            self.interpreter = other.interpreter.clone();
+        }
+        if other.script.is_some() {
+            self.script = other.script.clone();
+        }
+        if other.scaffold.is_some() {
+            self.scaffold = other.scaffold.clone();
+        }
+        if other.gitignore.is_some() {
+            self.gitignore = other.gitignore.clone();
+        }
+        if other.hooks.is_some() {
+            self.hooks = other.hooks.clone();
+        }
+        if other.markers.is_some() {
+            self.markers = other.markers.clone();
+        }
+        if other.editor.is_some() {
+            self.editor = other.editor.clone();
+        }
+    }
+
+    /// Whether `name` - an extension or interpreter - refers to this language
+    fn answers_to(&self, id: &str, name: &str) -> bool {
+        let interpreter = self.interpreter
+            .as_deref()
+            .and_then(|i| i.split_whitespace().last())
+            .and_then(|i| i.rsplit('/').next());
+
+        id == name
+            || self.ext() == Some(name)
+            || interpreter == Some(name)
+            || self.aliases.iter().any(|a| a.to_lowercase() == name)
+    }
+
+    pub fn ext(&self) -> Option<&str> {
+        self.ext.as_deref()
+    }
+}
+
+impl ScaffoldFile {
+    fn new(path: &str, contents: &str) -> Self {
+        Self { path: path.to_owned(), contents: contents.to_owned(), when: None }
+    }
+
+    fn when(mut self, cond: &str) -> Self {
+        self.when = Some(cond.to_owned());
+        self
+    }
+}
+
+impl Languages {
+    pub fn new(custom: &HashMap<String, Language>) -> Self {
+        let mut langs = builtin();
+
+        for (id, lang) in custom.iter() {
+            langs.entry(id.to_lowercase())
+                .or_default()
+                .merge(lang);
+        }
+
+        Self(langs)
+    }
+
+    /// Finds a language by its id or one of its aliases, ignoring case
+    pub fn resolve(&self, name: &str) -> BoilResult<(ProgType, &Language)> {
+        let lower = name.to_lowercase();
+
+        self.0
+            .iter()
+            .find(|(id, lang)| **id == lower || lang.aliases.iter().any(|a| a.to_lowercase() == lower))
+            .map(|(id, lang)| (ProgType::from(id.as_str()), lang))
+            .ok_or_else(|| BoilError::UnknownType(name.to_owned()))
+    }
+
+    /// Language of an existing entry
+    pub fn get(&self, prog_type: &ProgType) -> BoilResult<&Language> {
+        self.resolve(prog_type.as_str()).map(|(_, lang)| lang)
+    }
+
+    /// Name of the language of an entry to show, ie `JavaScript`
+    pub fn display_name(&self, prog_type: &ProgType) -> String {
+        match self.get(prog_type) {
+            Ok(Language { name: Some(n), .. }) => n.to_owned(),
+            _ => capitalize!(prog_type.to_string()),
+        }
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Guesses the language of a script from its extension or shebang, or of a
+    /// project directory from the marker files it contains
+    pub fn detect(&self, path: &Path) -> Option<ProgType> {
+        if path.is_dir() {
+            self.detect_project(path)
+        } else {
+            self.detect_script(path)
+        }
+    }
+
+    fn detect_script(&self, path: &Path) -> Option<ProgType> {
+        if let Some(ext) = path.extension() {
+            let ext = ext.to_string_lossy().to_lowercase();
+
+            if let Some(t) = self.find(&ext) {
+                return Some(t)
+            }
+        }
+
+        let file = fs::File::open(path).ok()?;
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).ok()?;
+
+        // ie `#!/usr/bin/env -S python3.12 -u` -> `python3.12`
+        let interpreter = line.strip_prefix("#!")?
+            .split_whitespace()
+            .filter(|w| !w.starts_with('-'))
+            .map(|w| w.rsplit('/').next().unwrap_or(w))
+            .find(|w| *w != "env")?
+            .to_lowercase();
+
+        self.find(&interpreter)
+            .or_else(|| self.find(interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')))
+    }
+
+    fn detect_project(&self, path: &Path) -> Option<ProgType> {
+        let best = self.0
+            .iter()
+            .map(|(id, lang)| {
+                let markers = lang.markers.as_deref().unwrap_or_default();
+                let found = markers.iter().filter(|m| path.join(m).exists()).count();
+                // Most markers found wins, ties go to the language missing the fewest
+                ((found, Reverse(markers.len() - found)), id)
+            })
+            .filter(|((found, _), _)| *found > 0)
+            .max_by_key(|(score, _)| *score);
+
+        if let Some((_, id)) = best {
+            return Some(ProgType::from(id.as_str()))
+        }
+
+        // No markers, go with the most common script type at the top level
+        let mut counts: HashMap<ProgType, usize> = HashMap::new();
+
+        for entry in fs::read_dir(path).ok()?.flatten() {
+            let file = entry.path();
+            if file.is_file() && file.extension().is_some() {
+                if let Some(t) = self.detect_script(&file) {
+                    *counts.entry(t).or_default() += 1;
+                }
+            }
+        }
+
+        counts.into_iter()
+            .max_by_key(|(_, c)| *c)
+            .map(|(t, _)| t)
+    }
+
+    fn find(&self, name: &str) -> Option<ProgType> {
+        self.0
+            .iter()
+            .find(|(id, lang)| lang.answers_to(id, name))
+            .map(|(id, _)| ProgType::from(id.as_str()))
+    }
+}
+
+fn lang(name: &str, aliases: &[&str], ext: &str, interpreter: Option<&str>) -> Language {
+    Language {
+        name: Some(name.to_owned()),
+        aliases: aliases.iter().map(|a| a.to_string()).collect(),
+        ext: Some(ext.to_owned()),
+        interpreter: interpreter.map(|i| i.to_owned()),
+        ..Default::default()
+    }
+}
+
+fn markers(files: &[&str]) -> Option<Vec<String>> {
+    Some(files.iter().map(|f| f.to_string()).collect())
+}
+
+fn snippet(name: &str) -> String {
+    gitignore::find(name).map(|(_, text)| text.to_string()).unwrap_or_default()
+}
+
+fn builtin() -> BTreeMap<String, Language> {
+    let mut langs = BTreeMap::new();
+
+    langs.insert("bash".to_string(), lang("Bash", &["sh", "shell", "zsh"], "sh", Some("/bin/bash")));
+
+    let mut python = lang("Python", &["py", "python3"], "py", Some("/usr/bin/env python3"));
+    python.scaffold = Some(vec![
+        ScaffoldFile::new("pyproject.toml", include_str!("py_pyproject.in")),
+        ScaffoldFile::new("README.md", include_str!("readme.in")),
+        ScaffoldFile::new("src/{{package}}/__init__.py", include_str!("py_init.in")),
+        ScaffoldFile::new("src/{{package}}/__main__.py", include_str!("py_main.in")),
+        ScaffoldFile::new("tests/test_{{package}}.py", include_str!("py_test.in")),
+    ]);
+    python.gitignore = Some(snippet("python"));
+    python.markers = markers(&["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"]);
+    langs.insert("python".to_string(), python);
+
+    let mut rust = lang("Rust", &["rs"], "rs", None);
+    rust.scaffold = Some(vec![
+        ScaffoldFile::new("Cargo.toml", include_str!("rs_cargo.in")),
+        ScaffoldFile::new("src/main.rs", include_str!("rs_main.in")).when("!lib"),
+        ScaffoldFile::new("src/lib.rs", include_str!("rs_lib.in")).when("lib"),
+    ]);
+    rust.gitignore = Some(snippet("rust"));
+    rust.markers = markers(&["Cargo.toml"]);
+    langs.insert("rust".to_string(), rust);
+
+    let mut js = lang("JavaScript", &["js", "node"], "js", Some("/usr/bin/env node"));
+    js.scaffold = Some(vec![
+        ScaffoldFile::new("package.json", include_str!("js_package.in")),
+        ScaffoldFile::new("index.js", include_str!("js_index.in")),
+    ]);
+    js.gitignore = Some(snippet("node"));
+    js.markers = markers(&["package.json"]);
+    langs.insert("javascript".to_string(), js);
+
+    let mut ts = lang("TypeScript", &["ts"], "ts", None);
+    ts.scaffold = Some(vec![
+        ScaffoldFile::new("package.json", include_str!("ts_package.in")),
+        ScaffoldFile::new("tsconfig.json", include_str!("ts_config.in")),
+        ScaffoldFile::new("src/index.ts", include_str!("js_index.in")),
+    ]);
+    ts.gitignore = Some(snippet("node"));
+    // Typescript projects also have a package.json, so match on both to outrank javascript
+    ts.markers = markers(&["package.json", "tsconfig.json"]);
+    langs.insert("typescript".to_string(), ts);
+
+    let mut go = lang("Go", &["golang"], "go", None);
+    go.scaffold = Some(vec![
+        ScaffoldFile::new("go.mod", "module {{slug}}\n\ngo 1.21\n"),
+        ScaffoldFile::new("main.go", include_str!("go_main.in")),
+    ]);
+    go.gitignore = Some(format!("/{{{{slug}}}}\n\n{}", snippet("go")));
+    go.markers = markers(&["go.mod"]);
+    langs.insert("go".to_string(), go);
+
+    let mut c = lang("C", &["h"], "c", None);
+    c.scaffold = Some(vec![
+        ScaffoldFile::new("Makefile", include_str!("c_makefile.in")),
+        ScaffoldFile::new("main.c", include_str!("c_main.in")),
+    ]);
+    c.gitignore = Some(format!("/{{{{slug}}}}\n\n{}", snippet("c")));
+    c.markers = markers(&["CMakeLists.txt"]);
+    langs.insert("c".to_string(), c);
+
+    let mut ruby = lang("Ruby", &["rb"], "rb", Some("/usr/bin/env ruby"));
+    ruby.markers = markers(&["Gemfile"]);
+    langs.insert("ruby".to_string(), ruby);
+
+    langs.insert("lua".to_string(), lang("Lua", &[], "lua", Some("/usr/bin/env lua")));
+
+    let mut perl = lang("Perl", &["pl"], "pl", Some("/usr/bin/env perl"));
+    perl.markers = markers(&["Makefile.PL", "cpanfile"]);
+    langs.insert("perl".to_string(), perl);
+
+    langs
+}
@@ -0,0 +1,31 @@
+use crate::error::{BoilError, BoilResult};
+
+/// SPDX identifiers of the bundled license texts
+const LICENSES: &[(&str, &str)] = &[
+    ("Apache-2.0", include_str!("licenses/Apache-2.0.in")),
+    ("BSD-2-Clause", include_str!("licenses/BSD-2-Clause.in")),
+    ("BSD-3-Clause", include_str!("licenses/BSD-3-Clause.in")),
+    ("GPL-3.0-only", include_str!("licenses/GPL-3.0.in")),
+    ("GPL-3.0-or-later", include_str!("licenses/GPL-3.0.in")),
+    ("ISC", include_str!("licenses/ISC.in")),
+    ("MIT", include_str!("licenses/MIT.in")),
+    ("MPL-2.0", include_str!("licenses/MPL-2.0.in")),
+    ("Unlicense", include_str!("licenses/Unlicense.in")),
+];
+
+/// Looks up a license by its SPDX id, ignoring case. Returns the canonical
+/// id and the license text, which may use `{{year}}` and `{{author}}`
+pub fn find(id: &str) -> BoilResult<(&'static str, &'static str)> {
+    // Deprecated SPDX id still commonly used
+    let id = if id.eq_ignore_ascii_case("GPL-3.0") { "GPL-3.0-only" } else { id };
+
+    LICENSES
+        .iter()
+        .find(|(spdx, _)| spdx.eq_ignore_ascii_case(id))
+        .copied()
+        .ok_or_else(|| BoilError::UnknownLicense(id.to_owned(), ids().join(", ")))
+}
+
+pub fn ids() -> Vec<&'static str> {
+    LICENSES.iter().map(|(spdx, _)| *spdx).collect()
+}
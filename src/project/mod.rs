@@ -1,58 +1,119 @@
 use std::fs;
-use std::process::Command;
-use std::io::Write;
 use std::path::{Path, PathBuf};
-use crate::config::ProgType;
-use crate::error::BoilResult;
+use crate::error::{BoilError, BoilResult};
 
+pub mod git;
+pub mod gitignore;
+mod editor;
+mod hooks;
+mod lang;
+pub mod license;
+mod plan;
+mod rollback;
+mod vars;
 
-pub fn create_program(path: &PathBuf, p_type: &ProgType) -> BoilResult<()> {
-    let mut file = fs::File::create(path)?;
+pub use editor::open;
+pub use hooks::{render_hook, run_hooks};
+pub use lang::{Language, Languages, ScaffoldFile};
+pub use plan::{Plan, PlannedFile};
+pub use rollback::Rollback;
+pub use vars::{package_name, placeholders, slugify, Vars};
 
-    match p_type {
-        ProgType::Python => file.write_all(b"#!/usr/bin/python")?,
-        ProgType::Bash => file.write_all(b"#!/bin/bash")?,
-        _ => {}
+
+/// Plans a new script - the language's shebang and script boilerplate
+pub fn plan_program(path: &Path, lang: &Language, vars: &Vars) -> Plan {
+    let mut content = String::new();
+
+    if let Some(i) = &lang.interpreter {
+        content.push_str(&format!("#!{}\n", i));
+    }
+
+    if let Some(s) = &lang.script {
+        content.push_str(&vars.render(s));
+    }
+
+    // Interpreted scripts can be run directly thanks to the shebang
+    let mut plan = Plan::new(path, false);
+    plan.add_file(PlannedFile::new("", content).executable(lang.interpreter.is_some()));
+
+    plan
+}
+
+/// Where the script at `path` would be linked to inside of `dir`
+pub fn link_path(path: &Path, dir: &Path, strip_ext: bool) -> PathBuf {
+    let name = match (strip_ext, path.file_stem()) {
+        (true, Some(stem)) => stem,
+        _ => path.file_name().unwrap_or_default()
     };
 
+    dir.join(name)
+}
+
+/// Symlinks the script at `path` to `link`, creating the link's directory if needed
+pub fn link_program(path: &Path, link: &Path, rollback: &mut Rollback) -> BoilResult<()> {
+    if link.symlink_metadata().is_ok() {
+        return Err(BoilError::PathExists(link.to_path_buf()))
+    }
+
+    if let Some(parent) = link.parent() {
+        rollback.create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(path, link)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(path, link)?;
+    rollback.track(link);
+
     Ok(())
 }
 
-pub fn create_project(path: &PathBuf, p_type: &ProgType) -> BoilResult<()> {
-    match p_type {
-        ProgType::Python => create_python_proj(path),
-        ProgType::Rust => todo!(),
-        _ => {
-            fs::create_dir_all(path)?;
-            Ok(())
+/// Plans a new project from the language's scaffold and `.gitignore`
+pub fn plan_project(path: &Path, lang: &Language, vars: &Vars) -> Plan {
+    let mut plan = Plan::new(path, true);
+
+    for file in lang.scaffold.iter().flatten() {
+        if let Some(w) = &file.when {
+            if !vars.check(w) {
+                continue
+            }
         }
+
+        let target = vars.render_path(Path::new(&file.path));
+        plan.add_file(PlannedFile::new(target, vars.render(&file.contents)));
     }
-    
+
+    plan_gitignore(&mut plan, lang, vars);
+
+    plan
 }
 
-fn create_python_proj(path: &PathBuf) -> BoilResult<()> {
-    fs::create_dir_all(path)?;
-    let mut dir = path.to_path_buf();
-    dir.push(".gitignore");
-    
-    let mut gitignore = fs::File::create(&dir)?;
-    let py_gi = include_bytes!("py_gitignore.in");
-    gitignore.write_all(py_gi);
+/// Adds the language's `.gitignore` to the project unless it already has one
+pub fn plan_gitignore(plan: &mut Plan, lang: &Language, vars: &Vars) {
+    if let (Some(gi), None) = (&lang.gitignore, plan.file(Path::new(".gitignore"))) {
+        plan.add_file(PlannedFile::new(".gitignore", vars.render(gi)));
+    }
+}
 
-    dir.pop();
-    dir.push("src");
-    fs::create_dir(&dir)?;
+/// Adds `contents` to the `.gitignore` in `dir`, skipping patterns it
+/// already has. Returns the path of the `.gitignore`
+pub fn extend_gitignore(dir: &Path, contents: &str) -> BoilResult<PathBuf> {
+    let path = dir.join(".gitignore");
 
-    dir.push("__init__.py");
-    fs::File::create(&dir);
-    
+    let existing = match path.is_file() {
+        true => fs::read_to_string(&path)?,
+        false => String::new()
+    };
 
-    Ok(())
+    fs::write(&path, gitignore::merge(&existing, contents))?;
+
+    Ok(path)
 }
 
-fn create_rust_project(path: &Path) -> BoilResult<()> {
-    Command::new("cargo")
-        .args(["new", path.to_str().unwrap()])
-        .output()?;
+/// Adds the text of the SPDX license `id` as `LICENSE`
+pub fn plan_license(plan: &mut Plan, id: &str, vars: &Vars) -> BoilResult<()> {
+    let (_, text) = license::find(id)?;
+    plan.add_file(PlannedFile::new("LICENSE", vars.render(text)));
+
     Ok(())
-}
\ No newline at end of file
+}
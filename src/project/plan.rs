@@ -0,0 +1,168 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BoilResult;
+use super::{gitignore, Rollback};
+
+/// A file to be generated for a new script or project
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedFile {
+    /// Path relative to the project root, empty for a script
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+    pub executable: bool,
+}
+
+/// Everything that will be written to disk for a new script or project,
+/// so it can be inspected before anything is created
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub path: PathBuf,
+    pub project: bool,
+    dirs: BTreeSet<PathBuf>,
+    files: Vec<PlannedFile>,
+}
+
+impl PlannedFile {
+    pub fn new(path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        Self { path: path.into(), contents: contents.into(), executable: false }
+    }
+
+    pub fn executable(mut self, executable: bool) -> Self {
+        self.executable = executable;
+        self
+    }
+}
+
+impl Plan {
+    pub fn new(path: &Path, project: bool) -> Self {
+        Self { path: path.to_path_buf(), project, dirs: BTreeSet::new(), files: vec![] }
+    }
+
+    /// Adds `file`, replacing any file already planned at the same path.
+    /// Files that are sets of lines, like `.gitignore`, are merged instead
+    pub fn add_file(&mut self, file: PlannedFile) {
+        match self.files.iter_mut().find(|f| f.path == file.path) {
+            Some(f) if is_line_set(&file.path) => {
+                let merged = gitignore::merge(&String::from_utf8_lossy(&f.contents), &String::from_utf8_lossy(&file.contents));
+                f.contents = merged.into_bytes();
+            },
+            Some(f) => *f = file,
+            None => self.files.push(file),
+        }
+    }
+
+    /// Adds an (possibly empty) directory relative to the project root
+    pub fn add_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.dirs.insert(dir.into());
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&PlannedFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    pub fn files(&self) -> &[PlannedFile] {
+        &self.files
+    }
+
+    /// Absolute path a planned file will be written to
+    pub fn target(&self, file: &PlannedFile) -> PathBuf {
+        if self.project {
+            self.path.join(&file.path)
+        } else {
+            self.path.to_owned()
+        }
+    }
+
+    /// Writes the planned files, recording everything created in `rollback`
+    pub fn write(&self, rollback: &mut Rollback) -> BoilResult<()> {
+        if self.project {
+            rollback.create_dir_all(&self.path)?;
+        }
+
+        for dir in self.dirs.iter() {
+            rollback.create_dir_all(&self.path.join(dir))?;
+        }
+
+        for file in self.files.iter() {
+            let target = self.target(file);
+
+            if let Some(parent) = target.parent() {
+                rollback.create_dir_all(parent)?;
+            }
+
+            rollback.write(&target, &file.contents)?;
+
+            if file.executable {
+                make_executable(&target)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The planned files as an indented tree, ie
+    ///
+    /// ```text
+    /// my-project/
+    ///   src/
+    ///     main.rs
+    ///   Cargo.toml
+    /// ```
+    pub fn tree(&self) -> String {
+        if !self.project {
+            return format!("{}\n", self.path.to_string_lossy())
+        }
+
+        let mut entries: BTreeSet<(PathBuf, bool)> = BTreeSet::new();
+
+        for dir in self.dirs.iter() {
+            for ancestor in dir.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+                entries.insert((ancestor.to_path_buf(), true));
+            }
+        }
+
+        for file in self.files.iter() {
+            for ancestor in file.path.ancestors().skip(1).filter(|a| !a.as_os_str().is_empty()) {
+                entries.insert((ancestor.to_path_buf(), true));
+            }
+            entries.insert((file.path.to_owned(), false));
+        }
+
+        let root = self.path.file_name().unwrap_or_default().to_string_lossy();
+        let mut out = format!("{}/\n", root);
+
+        for (path, is_dir) in entries {
+            let depth = path.components().count();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let suffix = if is_dir { "/" } else { "" };
+            out.push_str(&format!("{}{}{}\n", "  ".repeat(depth), name, suffix));
+        }
+
+        out
+    }
+}
+
+/// Files where each line is an independent entry and order doesn't matter
+const LINE_SETS: [&str; 4] = [".gitignore", ".dockerignore", ".npmignore", ".prettierignore"];
+
+fn is_line_set(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| LINE_SETS.iter().any(|l| n == *l))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> BoilResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(perms.mode() | 0o111);
+    fs::set_permissions(path, perms)?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> BoilResult<()> {
+    Ok(())
+}
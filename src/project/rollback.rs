@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BoilResult;
+
+/// Records every path created while adding a new entry so a failure part way
+/// through can put the filesystem back the way it was
+#[derive(Debug, Default)]
+pub struct Rollback {
+    created: Vec<PathBuf>,
+}
+
+impl Rollback {
+    /// Marks `path` as created by boil
+    pub fn track(&mut self, path: &Path) {
+        self.created.push(path.to_path_buf());
+    }
+
+    /// Like `fs::create_dir_all`, tracking each directory that did not exist yet
+    pub fn create_dir_all(&mut self, path: &Path) -> BoilResult<()> {
+        let missing: Vec<&Path> = path.ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_err())
+            .collect();
+
+        for dir in missing.into_iter().rev() {
+            fs::create_dir(dir)?;
+            self.track(dir);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `contents` to `path`, tracking the file if it is new
+    pub fn write(&mut self, path: &Path, contents: &[u8]) -> BoilResult<()> {
+        let existed = path.symlink_metadata().is_ok();
+        fs::write(path, contents)?;
+
+        if !existed {
+            self.track(path);
+        }
+
+        Ok(())
+    }
+
+    /// Removes everything created so far, newest first. Failures are reported
+    /// but don't stop the rest from being removed
+    pub fn undo(self) {
+        for path in self.created.into_iter().rev() {
+            let res = match path.symlink_metadata() {
+                Ok(m) if m.is_dir() => fs::remove_dir_all(&path),
+                Ok(_) => fs::remove_file(&path),
+                // Already removed along with its parent
+                Err(_) => continue,
+            };
+
+            if let Err(e) = res {
+                eprintln!("boil warning: unable to remove {} - {e}", path.to_string_lossy());
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Program;
+use crate::utils::utc_datetime;
+
+/// Placeholder values available to generated files and file names as `{{key}}`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vars(HashMap<String, String>);
+
+impl Vars {
+    /// Collects the variables describing `program`
+    pub fn new(program: &Program, author: &str) -> Self {
+        let mut vars = Self::default();
+
+        vars.insert("name", &program.name);
+        vars.insert("slug", &slugify(&program.name, '-'));
+        vars.insert("package", &package_name(&program.name));
+        vars.insert("description", program.description.as_deref().unwrap_or_default());
+        vars.insert("tags", &program.tags.clone().unwrap_or_default().join(", "));
+        let date = today();
+        vars.insert("date", &date);
+        vars.insert("year", &date[..4]);
+        vars.insert("author", author);
+        vars.insert("type", &program.prog_type.as_str().to_lowercase());
+        vars.insert("license", program.license.as_deref().unwrap_or_default());
+
+        vars
+    }
+
+    pub fn insert(&mut self, key: &str, val: &str) {
+        self.0.insert(key.to_owned(), val.to_owned());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|v| v.as_str())
+    }
+
+    /// Whether the variable `key` holds a truthy value
+    pub fn is_set(&self, key: &str) -> bool {
+        !matches!(self.get(key), None | Some("" | "false" | "0" | "no"))
+    }
+
+    /// Evaluates a condition of the form `key`, `!key`, `key == value` or `key != value`
+    pub fn check(&self, cond: &str) -> bool {
+        if let Some((key, val)) = cond.split_once("!=") {
+            return self.get(key.trim()).unwrap_or_default() != val.trim()
+        }
+        if let Some((key, val)) = cond.split_once("==") {
+            return self.get(key.trim()).unwrap_or_default() == val.trim()
+        }
+
+        match cond.trim().strip_prefix('!') {
+            Some(key) => !self.is_set(key.trim()),
+            None => self.is_set(cond.trim()),
+        }
+    }
+
+    /// Replaces every known `{{key}}` in `input` with its value. Unknown
+    /// placeholders are left untouched. `{{key|toml}}`, `{{key|json}}` and `{{key|py}}`
+    /// escape the value for use inside a double quoted string of that language
+    pub fn render(&self, input: &str) -> String {
+        self.render_with(input, str::to_owned)
+    }
+
+    /// Like `render`, passing every value through `escape` first
+    pub fn render_with(&self, input: &str, escape: fn(&str) -> String) -> String {
+        let mut out = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+
+            match after.find("}}") {
+                Some(end) => {
+                    match self.filtered(&after[..end]) {
+                        Some(val) => out.push_str(&escape(&val)),
+                        None => out.push_str(&rest[start..start + end + 4]),
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    out.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+
+        out.push_str(rest);
+        out
+    }
+
+    /// Value of the placeholder `key` or `key|filter`
+    fn filtered(&self, placeholder: &str) -> Option<String> {
+        let (key, filter) = match placeholder.split_once('|') {
+            Some((key, filter)) => (key.trim(), Some(filter.trim())),
+            None => (placeholder.trim(), None),
+        };
+        let val = self.get(key)?;
+
+        match filter {
+            None => Some(val.to_owned()),
+            Some("toml" | "json" | "py") => Some(escape_string(val)),
+            Some(_) => None,
+        }
+    }
+
+    /// Renders each component of a relative path, ie `src/{{name}}/__init__.py`
+    pub fn render_path(&self, path: &Path) -> PathBuf {
+        path.iter()
+            .map(|c| self.render(&c.to_string_lossy()))
+            .collect()
+    }
+}
+
+/// Keys of the `{{key}}` placeholders used in `input`, in order of appearance
+pub fn placeholders(input: &str) -> Vec<String> {
+    let mut keys: Vec<String> = vec![];
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else { break };
+        let key = after[..end].split('|').next().unwrap_or_default().trim();
+
+        if !key.is_empty() && !keys.iter().any(|k| k == key) {
+            keys.push(key.to_owned());
+        }
+        rest = &after[end + 2..];
+    }
+
+    keys
+}
+
+/// Escapes `val` for a double quoted TOML, JSON or Python string, which share their escapes
+fn escape_string(val: &str) -> String {
+    let mut out = String::with_capacity(val.len());
+
+    for c in val.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Quotes `val` as a single shell word, ie `it's` -> `'it'\''s'`
+pub fn shell_quote(val: &str) -> String {
+    format!("'{}'", val.replace('\'', r"'\''"))
+}
+
+/// Lowercases `name` and joins its alphanumeric runs with `sep`,
+/// ie `My Project` -> `my-project`
+pub fn slugify(name: &str, sep: char) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join(&sep.to_string())
+}
+
+/// Name usable as a python package/module, ie `My-Project` -> `my_project`
+pub fn package_name(name: &str) -> String {
+    let slug = slugify(name, '_');
+
+    match slug.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("_{}", slug),
+        _ => slug
+    }
+}
+
+/// Current UTC date as YYYY-MM-DD
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    utc_datetime(secs)[..10].to_owned()
+}
@@ -0,0 +1,180 @@
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::config::Config;
+use crate::error::{BoilError, BoilResult};
+
+/// A file format the config can be kept in
+pub trait Storage {
+    /// Reads a raw config, to be migrated before it is deserialized
+    fn load(&self, content: &str) -> BoilResult<Table>;
+
+    fn dump(&self, config: &Config) -> BoilResult<String>;
+
+    /// Writes part of a config, ie a single entry, the way `dump` would
+    fn dump_value(&self, value: &Value) -> BoilResult<String>;
+}
+
+/// The default, and the only format that keeps the order of the config's fields
+pub struct TomlStorage;
+
+/// Keys are written in alphabetical order
+pub struct JsonStorage;
+
+/// Keys are written in alphabetical order
+pub struct YamlStorage;
+
+impl Storage for TomlStorage {
+    fn load(&self, content: &str) -> BoilResult<Table> {
+        Ok(toml::from_str(content)?)
+    }
+
+    fn dump(&self, config: &Config) -> BoilResult<String> {
+        Ok(toml::to_string_pretty(config)?)
+    }
+
+    fn dump_value(&self, value: &Value) -> BoilResult<String> {
+        Ok(toml::to_string_pretty(value)?)
+    }
+}
+
+// JSON and YAML go through a TOML value on the way out, so unset fields are left out
+// rather than written as null and anything they hold can be converted back to TOML.
+// TOML has no null either, so nulls written by other tools are dropped on the way in
+
+impl Storage for JsonStorage {
+    fn load(&self, content: &str) -> BoilResult<Table> {
+        let mut value: serde_json::Value = serde_json::from_str(content)?;
+        drop_json_nulls(&mut value);
+        Ok(Table::deserialize(value)?)
+    }
+
+    fn dump(&self, config: &Config) -> BoilResult<String> {
+        self.dump_value(&Value::try_from(config)?)
+    }
+
+    fn dump_value(&self, value: &Value) -> BoilResult<String> {
+        let mut out = serde_json::to_string_pretty(value)?;
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+impl Storage for YamlStorage {
+    fn load(&self, content: &str) -> BoilResult<Table> {
+        let mut value: serde_norway::Value = serde_norway::from_str(content)?;
+        drop_yaml_nulls(&mut value);
+        Ok(Table::deserialize(value)?)
+    }
+
+    fn dump(&self, config: &Config) -> BoilResult<String> {
+        self.dump_value(&Value::try_from(config)?)
+    }
+
+    fn dump_value(&self, value: &Value) -> BoilResult<String> {
+        Ok(serde_norway::to_string(value)?)
+    }
+}
+
+fn drop_json_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(drop_json_nulls);
+        },
+        serde_json::Value::Array(items) => {
+            items.retain(|v| !v.is_null());
+            items.iter_mut().for_each(drop_json_nulls);
+        },
+        _ => {}
+    }
+}
+
+fn drop_yaml_nulls(value: &mut serde_norway::Value) {
+    match value {
+        serde_norway::Value::Mapping(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(drop_yaml_nulls);
+        },
+        serde_norway::Value::Sequence(items) => {
+            items.retain(|v| !v.is_null());
+            items.iter_mut().for_each(drop_yaml_nulls);
+        },
+        serde_norway::Value::Tagged(tagged) => drop_yaml_nulls(&mut tagged.value),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// Format of the config at `path`, taken from its extension. Files without a known
+    /// extension are in the format set by `BOIL_CONFIG_FORMAT`, or TOML
+    pub fn of(path: &Path) -> BoilResult<Self> {
+        let ext = path.extension().unwrap_or_default().to_string_lossy();
+
+        if let Ok(format) = Format::from_str(&ext) {
+            return Ok(format)
+        }
+
+        match env::var("BOIL_CONFIG_FORMAT") {
+            Ok(f) => Format::from_str(&f),
+            Err(_) => Ok(Format::Toml),
+        }
+    }
+
+    /// Writes `value`, part of a config such as a single entry, in this format. TOML is
+    /// written straight from `value` so its fields keep the order they have in the config
+    pub fn dump<T: Serialize>(&self, value: &T) -> BoilResult<String> {
+        match self {
+            Format::Toml => Ok(toml::to_string_pretty(value)?),
+            f => f.storage().dump_value(&Value::try_from(value)?),
+        }
+    }
+
+    pub fn storage(&self) -> &'static dyn Storage {
+        match self {
+            Format::Toml => &TomlStorage,
+            Format::Json => &JsonStorage,
+            Format::Yaml => &YamlStorage,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = BoilError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "toml" => Ok(Format::Toml),
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            f => Err(BoilError::UnknownFormat(f.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
@@ -5,6 +5,7 @@ use prettytable::{Cell, Row, Table};
 use crate::args::{FilterOpt, ListArgs, ListOpts, SortOpt};
 use crate::config::{Field, Program};
 use crate::error::{BoilError, BoilResult};
+use crate::project::Languages;
 use crate::utils::capitalize;
 
 struct TableOpts {
@@ -33,6 +34,7 @@ impl TableOpts {
                 "t" | "type" => ListOpts::Type,
                 "d" | "description" => ListOpts::Description,
                 "T" | "tags" => ListOpts::Tags,
+                "l" | "license" => ListOpts::License,
                 f => return Err(BoilError::ListFormat(f.to_string())),
             };
             list_args.push(o);
@@ -50,6 +52,7 @@ impl TableOpts {
                     "t" | "type" => ListOpts::Type,
                     "d" | "description" => ListOpts::Description,
                     "T" | "tags" => ListOpts::Tags,
+                    "l" | "license" => ListOpts::License,
                     f => return Err(BoilError::SortFormat(f.to_string())),
                 };
 
@@ -104,6 +107,7 @@ impl BoilTable {
                 ListOpts::Project => Cell::new("Project").style_spec("b"),
                 ListOpts::Tags => Cell::new("Tags").style_spec("b"),
                 ListOpts::Type => Cell::new("Type").style_spec("b"),
+                ListOpts::License => Cell::new("License").style_spec("b"),
             };
             first_row.push(o);
         }
@@ -111,7 +115,7 @@ impl BoilTable {
         self.table.add_row(Row::new(first_row));
     }
 
-    pub fn display(&mut self, mut entries: Vec<Program>) {
+    pub fn display(&mut self, mut entries: Vec<Program>, languages: &Languages) {
         if let Some(s) = &self.opts.sort_arg {
             entries.sort_by_cached_key(|k| get_sort_key(k, s))
         };
@@ -148,7 +152,8 @@ impl BoilTable {
                             Cell::new("None").style_spec("b")
                         }
                     }
-                    ListOpts::Type => Cell::new(&format!("{:?}", e.prog_type)).style_spec("b"),
+                    ListOpts::Type => Cell::new(&languages.display_name(&e.prog_type)).style_spec("b"),
+                    ListOpts::License => Cell::new(e.license.as_ref().unwrap_or(&String::from("None"))),
                 };
                 row.push(o);
             }
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BoilResult;
+
+/// Directories that only hold version control data, dependencies or build
+/// outputs. Never captured, even without a `.gitignore` saying so
+const SKIPPED: &[&str] = &[
+    ".git", "target", "node_modules", "__pycache__", ".venv", "venv",
+    "dist", "build", ".tox", ".mypy_cache", ".pytest_cache",
+];
+
+/// Copies the project at `src` to `dest`, leaving out ignored files and
+/// replacing each `(from, to)` of `subs` in file names and text files
+pub fn capture_tree(src: &Path, dest: &Path, subs: &[(String, String)]) -> BoilResult<()> {
+    let mut ignore = Ignore::default();
+    walk(src, Path::new(""), dest, subs, &mut ignore)
+}
+
+fn walk(src: &Path, rel: &Path, dest: &Path, subs: &[(String, String)], ignore: &mut Ignore) -> BoilResult<()> {
+    let dir = src.join(rel);
+    let rules = ignore.load(&dir.join(".gitignore"), rel)?;
+
+    fs::create_dir_all(dest.join(substitute(&rel.to_string_lossy(), subs)))?;
+
+    let mut entries = fs::read_dir(&dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let path = rel.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        let is_dir = file_type.is_dir();
+
+        if file_type.is_symlink() || ignore.is_ignored(&path, is_dir) {
+            continue
+        }
+
+        if is_dir {
+            if !SKIPPED.iter().any(|s| entry.file_name() == *s) {
+                walk(src, &path, dest, subs, ignore)?;
+            }
+            continue
+        }
+
+        let target = dest.join(substitute(&path.to_string_lossy(), subs));
+
+        match String::from_utf8(fs::read(entry.path())?) {
+            Ok(text) => {
+                fs::write(&target, substitute(&text, subs))?;
+                fs::set_permissions(&target, entry.metadata()?.permissions())?;
+            },
+            Err(_) => {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+    }
+
+    // Rules of a .gitignore only apply below its own directory
+    ignore.rules.truncate(ignore.rules.len() - rules);
+
+    Ok(())
+}
+
+/// Replaces every `from` of `subs` in `text`, preferring the longest at each position.
+/// Only whole words are replaced, so `app` is left alone inside of `application`
+fn substitute(text: &str, subs: &[(String, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        let best = match prev.is_some_and(is_word) {
+            true => None,
+            false => subs.iter()
+                .filter(|(from, _)| !from.is_empty() && rest.starts_with(from.as_str()))
+                .filter(|(from, _)| !rest[from.len()..].chars().next().is_some_and(is_word))
+                .max_by_key(|(from, _)| from.len()),
+        };
+
+        if let Some((from, to)) = best {
+            out.push_str(to);
+            prev = from.chars().last();
+            rest = &rest[from.len()..];
+            continue
+        }
+
+        out.push(c);
+        prev = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    out
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// A single `.gitignore` pattern
+#[derive(Debug)]
+struct Rule {
+    /// Directory of the `.gitignore`, relative to the project root
+    base: PathBuf,
+    pattern: String,
+    negate: bool,
+    dir_only: bool,
+    /// Matched against the whole path rather than only the file name
+    anchored: bool,
+}
+
+/// The `.gitignore` rules in effect for the directory being walked
+#[derive(Debug, Default)]
+struct Ignore {
+    rules: Vec<Rule>,
+}
+
+impl Ignore {
+    /// Adds the rules of the `.gitignore` at `file`, returning how many were added
+    fn load(&mut self, file: &Path, base: &Path) -> BoilResult<usize> {
+        if !file.is_file() {
+            return Ok(0)
+        }
+
+        let before = self.rules.len();
+
+        for line in fs::read_to_string(file)?.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let (negate, line) = match line.strip_prefix('!') {
+                Some(l) => (true, l),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(l) => (true, l),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+
+            self.rules.push(Rule {
+                base: base.to_path_buf(),
+                pattern: line.trim_start_matches('/').to_owned(),
+                negate,
+                dir_only,
+                anchored,
+            });
+        }
+
+        Ok(self.rules.len() - before)
+    }
+
+    /// Whether `path`, relative to the project root, is ignored. The last matching rule wins
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+
+        for rule in self.rules.iter() {
+            if rule.dir_only && !is_dir {
+                continue
+            }
+
+            let Ok(rel) = path.strip_prefix(&rule.base) else { continue };
+
+            let matched = if rule.anchored {
+                let rel: Vec<String> = rel.iter().map(|c| c.to_string_lossy().into_owned()).collect();
+                glob(&rule.pattern, &rel.join("/"))
+            } else {
+                rel.file_name().is_some_and(|n| glob(&rule.pattern, &n.to_string_lossy()))
+            };
+
+            if matched {
+                ignored = !rule.negate;
+            }
+        }
+
+        ignored
+    }
+}
+
+/// Matches `text` against a gitignore style glob - `*` and `?` stay within a
+/// path component, `**` spans any number of them and `[...]` is a character class
+fn glob(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    glob_at(&p, &t)
+}
+
+fn glob_at(p: &[char], t: &[char]) -> bool {
+    match p.first() {
+        None => t.is_empty(),
+        Some('*') if p.get(1) == Some(&'*') => {
+            // `**/` also matches no directories at all
+            let rest = if p.get(2) == Some(&'/') { &p[3..] } else { &p[2..] };
+            (0..=t.len()).any(|i| glob_at(rest, &t[i..]))
+        },
+        Some('*') => {
+            (0..=t.len())
+                .take_while(|&i| i == 0 || t[i - 1] != '/')
+                .any(|i| glob_at(&p[1..], &t[i..]))
+        },
+        Some('?') => matches!(t.first(), Some(c) if *c != '/') && glob_at(&p[1..], &t[1..]),
+        Some('[') => {
+            let Some(end) = p.iter().skip(1).position(|c| *c == ']').map(|i| i + 1) else {
+                return t.first() == Some(&'[') && glob_at(&p[1..], &t[1..])
+            };
+            let Some(c) = t.first() else { return false };
+
+            let class = &p[1..end];
+            let (negate, class) = match class.first() {
+                Some('!' | '^') => (true, &class[1..]),
+                _ => (false, class),
+            };
+
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= *c && *c <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == *c;
+                    i += 1;
+                }
+            }
+
+            found != negate && glob_at(&p[end + 1..], &t[1..])
+        },
+        Some(c) => t.first() == Some(c) && glob_at(&p[1..], &t[1..]),
+    }
+}
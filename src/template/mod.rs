@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BoilError, BoilResult};
+use crate::project::{package_name, placeholders, slugify, Plan, PlannedFile, Rollback, Vars};
+
+mod capture;
+mod prompt;
+mod source;
+
+pub use prompt::{answer, Prompt, PromptKind};
+
+/// Optional file at the root of a template describing how to use it.
+/// It is not copied into new projects
+pub const MANIFEST: &str = "boil.toml";
+
+/// A user defined project layout - a directory inside the template path
+/// whose file tree is copied into each new project created from it
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+    pub manifest: Manifest,
+    /// The templates listed in `extends`, already resolved
+    pub layers: Vec<Template>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Manifest {
+    /// Short summary shown by `boil template list`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Templates this one is built on top of, applied in order before its own files
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+    /// Commands run in the new project after the template is copied
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<String>,
+    /// Questions whose answers become variables of the template
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub prompts: Vec<Prompt>,
+    /// Conditions deciding whether a file or directory of the template is included,
+    /// ie `"Dockerfile" = "docker"` or `"setup.cfg" = "python != 3.12"`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub when: HashMap<String, String>,
+}
+
+impl Template {
+    /// Resolves `template` - either the name of a template in the template directory
+    /// `dir`, or a git repository/local directory which is cloned into `cache` first
+    pub fn resolve(template: &str, reference: Option<&str>, dir: &Path, cache: &Path) -> BoilResult<Self> {
+        Self::resolve_layered(template, reference, dir, cache, false, &mut vec![])
+    }
+
+    /// Like `resolve`, but git sources are only taken from the cache as they are,
+    /// leaving the disk untouched
+    pub fn resolve_cached(template: &str, reference: Option<&str>, dir: &Path, cache: &Path) -> BoilResult<Self> {
+        Self::resolve_layered(template, reference, dir, cache, true, &mut vec![])
+    }
+
+    /// Resolves `template` along with every template it extends. `seen` holds the
+    /// templates currently being resolved to catch templates extending themselves
+    fn resolve_layered(
+        template: &str,
+        reference: Option<&str>,
+        dir: &Path,
+        cache: &Path,
+        cached: bool,
+        seen: &mut Vec<PathBuf>
+    ) -> BoilResult<Self> {
+        let mut t = Self::resolve_single(template, reference, dir, cache, cached)?;
+        let key = t.key();
+
+        if seen.contains(&key) {
+            return Err(BoilError::TemplateCycle(t.name))
+        }
+        seen.push(key);
+
+        for parent in t.manifest.extends.iter() {
+            // Relative paths are relative to the template extending them
+            let parent = match parent.starts_with('.') {
+                true => t.path.join(parent).to_string_lossy().into_owned(),
+                false => parent.to_owned()
+            };
+            t.layers.push(Self::resolve_layered(&parent, None, dir, cache, cached, seen)?);
+        }
+
+        seen.pop();
+        Ok(t)
+    }
+
+    fn resolve_single(template: &str, reference: Option<&str>, dir: &Path, cache: &Path, cached: bool) -> BoilResult<Self> {
+        if !source::is_source(template) {
+            if reference.is_some() {
+                return Err(BoilError::InvalidTemplate(template.to_owned()))
+            }
+            return Self::find(dir, template)
+        }
+
+        let local = Path::new(template);
+
+        if local.is_dir() && !source::is_repo(local) {
+            // Plain directory, use it as is
+            if reference.is_some() {
+                return Err(BoilError::InvalidTemplate(template.to_owned()))
+            }
+            return Self::load(template, local.to_path_buf())
+        }
+
+        // Relative paths would otherwise be resolved from inside of the cache
+        let url = match local.canonicalize() {
+            Ok(p) if local.exists() => p.to_string_lossy().into_owned(),
+            _ => template.to_owned()
+        };
+
+        let path = match cached {
+            true => source::cached(&url, reference, cache)?,
+            false => source::fetch(&url, reference, cache)?,
+        };
+
+        Self::load(template, path)
+    }
+
+    /// Looks up the template `name` inside of the template directory `dir`
+    pub fn find(dir: &Path, name: &str) -> BoilResult<Self> {
+        let path = dir.join(valid_name(name)?);
+
+        if !path.is_dir() {
+            return Err(BoilError::TemplateNotFound(name.to_owned()))
+        }
+
+        Self::load(name, path)
+    }
+
+    fn load(name: &str, path: PathBuf) -> BoilResult<Self> {
+        let manifest_path = path.join(MANIFEST);
+        let manifest = if manifest_path.is_file() {
+            toml::from_str(&fs::read_to_string(manifest_path)?)?
+        } else {
+            Manifest::default()
+        };
+
+        Ok(Self { name: name.to_owned(), path, manifest, layers: vec![] })
+    }
+
+    /// Adds the files of each layer and then the template's own files to `plan`,
+    /// so later files override earlier ones. Placeholders in file names and
+    /// text file contents are expanded from `vars`
+    pub fn plan(&self, plan: &mut Plan, vars: &Vars) -> BoilResult<()> {
+        self.plan_layers(plan, vars, true)
+    }
+
+    /// Adds every file of the template to `plan` as is - placeholders are left
+    /// alone and `when` conditions are ignored
+    pub fn plan_all(&self, plan: &mut Plan) -> BoilResult<()> {
+        self.plan_layers(plan, &Vars::default(), false)
+    }
+
+    fn plan_layers(&self, plan: &mut Plan, vars: &Vars, conditional: bool) -> BoilResult<()> {
+        for t in self.linearized() {
+            let when = match conditional {
+                true => t.manifest.when.to_owned(),
+                false => HashMap::new()
+            };
+
+            plan_tree(&t.path, &t.path, Path::new(""), plan, vars, &when)?;
+        }
+
+        Ok(())
+    }
+
+    /// The template's layers in the order they are applied, ending with the template
+    /// itself. A template extended by more than one layer is only applied the first time
+    fn linearized(&self) -> Vec<&Template> {
+        let mut order = vec![];
+        self.linearize(&mut order, &mut vec![]);
+        order
+    }
+
+    fn linearize<'a>(&'a self, order: &mut Vec<&'a Template>, seen: &mut Vec<PathBuf>) {
+        for layer in self.layers.iter() {
+            layer.linearize(order, seen);
+        }
+
+        let key = self.key();
+        if !seen.contains(&key) {
+            seen.push(key);
+            order.push(self);
+        }
+    }
+
+    /// Identifies the template's directory however it was reached
+    fn key(&self) -> PathBuf {
+        self.path.canonicalize().unwrap_or_else(|_| self.path.to_owned())
+    }
+
+    /// Prompts of every layer and then the template's own. A prompt of a later
+    /// layer replaces an earlier one with the same name
+    pub fn prompts(&self) -> Vec<Prompt> {
+        let mut prompts: Vec<Prompt> = vec![];
+
+        for p in self.linearized().into_iter().flat_map(|t| t.manifest.prompts.iter().cloned()) {
+            match prompts.iter_mut().find(|e| e.name == p.name) {
+                Some(e) => *e = p,
+                None => prompts.push(p),
+            }
+        }
+
+        prompts
+    }
+
+    /// Every template in the template directory `dir`, sorted by name. Templates
+    /// whose manifest can't be read are reported and left out
+    pub fn list(dir: &Path) -> BoilResult<Vec<Self>> {
+        if !dir.is_dir() {
+            return Ok(vec![])
+        }
+
+        let mut templates = vec![];
+
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_dir() {
+                continue
+            }
+
+            // One broken template shouldn't hide the others
+            let name = entry.file_name().to_string_lossy().into_owned();
+            match Self::load(&name, entry.path()) {
+                Ok(t) => templates.push(t),
+                Err(e) => eprintln!("boil warning: skipping template '{}' - {e}", name),
+            }
+        }
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// Copies the directory `src` into the template directory `dir` as the template `name`
+    pub fn add(dir: &Path, name: &str, src: &Path) -> BoilResult<Self> {
+        let path = dir.join(valid_name(name)?);
+
+        if !src.is_dir() {
+            return Err(BoilError::InvalidPath(src.to_path_buf()))
+        }
+
+        copy_template(src, &path)?;
+        Self::load(name, path)
+    }
+
+    /// Snapshots the project at `src` into the template directory `dir` as the template
+    /// `name`. Ignored files and build outputs are left out, and `entry` - the name of
+    /// the project - is replaced with placeholders so it can be reused
+    pub fn capture(dir: &Path, name: &str, src: &Path, entry: &str) -> BoilResult<Self> {
+        let path = dir.join(valid_name(name)?);
+
+        if !src.is_dir() {
+            return Err(BoilError::InvalidPath(src.to_path_buf()))
+        }
+        if path.symlink_metadata().is_ok() {
+            return Err(BoilError::PathExists(path))
+        }
+
+        let mut subs: Vec<(String, String)> = vec![];
+        for (from, to) in [(entry.to_owned(), "{{name}}"), (slugify(entry, '-'), "{{slug}}"), (package_name(entry), "{{package}}")] {
+            // The name wins when its slug or package name is the same
+            if !subs.iter().any(|(f, _)| *f == from) {
+                subs.push((from, to.to_owned()));
+            }
+        }
+
+        let res = capture::capture_tree(src, &path, &subs).and_then(|_| {
+            let manifest = path.join(MANIFEST);
+            if !manifest.exists() {
+                let description = toml::Value::String(format!("Captured from {}", entry));
+                fs::write(manifest, format!("description = {}\n", description))?;
+            }
+            Ok(())
+        });
+
+        if let Err(e) = res {
+            let _ = fs::remove_dir_all(&path);
+            return Err(e)
+        }
+
+        Self::load(name, path)
+    }
+
+    /// Copies the template's files to `dest`, which must not exist yet. A template
+    /// extending others is exported with its layers applied so it works on its own
+    pub fn export(&self, dest: &Path) -> BoilResult<()> {
+        if self.layers.is_empty() {
+            return copy_template(&self.path, dest)
+        }
+        if dest.symlink_metadata().is_ok() {
+            return Err(BoilError::PathExists(dest.to_path_buf()))
+        }
+
+        if let Err(e) = self.export_flattened(dest) {
+            let _ = fs::remove_dir_all(dest);
+            return Err(e)
+        }
+
+        Ok(())
+    }
+
+    /// Writes the files of every layer to `dest` along with a manifest holding the
+    /// hooks, prompts and conditions of all of them. A condition of a later layer
+    /// replaces an earlier one for the same path
+    fn export_flattened(&self, dest: &Path) -> BoilResult<()> {
+        let mut plan = Plan::new(dest, true);
+        self.plan_all(&mut plan)?;
+
+        let mut manifest = Manifest {
+            description: self.manifest.description.to_owned(),
+            hooks: self.hooks(),
+            prompts: self.prompts(),
+            ..Manifest::default()
+        };
+        for t in self.linearized() {
+            manifest.when.extend(t.manifest.when.to_owned());
+        }
+
+        plan.add_file(PlannedFile::new(MANIFEST, toml::to_string(&manifest)?));
+        plan.write(&mut Rollback::default())
+    }
+
+    /// Every `{{key}}` used by the template and its layers, in file names,
+    /// text files and hooks
+    pub fn variables(&self) -> BoilResult<Vec<String>> {
+        let mut plan = Plan::new(&self.path, true);
+        self.plan_all(&mut plan)?;
+
+        let mut keys: Vec<String> = vec![];
+        let texts = plan.files().iter()
+            .flat_map(|f| [f.path.to_string_lossy().into_owned(), String::from_utf8_lossy(&f.contents).into_owned()])
+            .chain(self.hooks());
+
+        for text in texts {
+            for key in placeholders(&text) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+
+    /// Hooks of every layer followed by the template's own
+    pub fn hooks(&self) -> Vec<String> {
+        self.linearized().into_iter().flat_map(|t| t.manifest.hooks.iter().cloned()).collect()
+    }
+}
+
+/// Checks `name` is a single path component so templates stay inside the template directory
+fn valid_name(name: &str) -> BoilResult<&str> {
+    let mut components = Path::new(name).components();
+
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(name),
+        _ => Err(BoilError::InvalidTemplate(name.to_owned()))
+    }
+}
+
+/// Copies the tree at `src` to the new directory `dest`, leaving out `.git`.
+/// Nothing is left behind at `dest` if copying fails
+fn copy_template(src: &Path, dest: &Path) -> BoilResult<()> {
+    if dest.symlink_metadata().is_ok() {
+        return Err(BoilError::PathExists(dest.to_path_buf()))
+    }
+
+    if let Err(e) = copy_tree(src, dest) {
+        let _ = fs::remove_dir_all(dest);
+        return Err(e)
+    }
+
+    Ok(())
+}
+
+fn copy_tree(src: &Path, dest: &Path) -> BoilResult<()> {
+    fs::create_dir_all(dest)?;
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+
+        if entry.file_name() == ".git" {
+            continue
+        }
+
+        let target = dest.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn plan_tree(
+    root: &Path,
+    src: &Path,
+    rel: &Path,
+    plan: &mut Plan,
+    vars: &Vars,
+    when: &HashMap<String, String>
+) -> BoilResult<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if src == root && (entry.file_name() == MANIFEST || entry.file_name() == ".git") {
+            continue
+        }
+
+        if !included(path.strip_prefix(root).unwrap_or(&path), vars, when) {
+            continue
+        }
+
+        let target = rel.join(vars.render_path(Path::new(&entry.file_name())));
+
+        if entry.file_type()?.is_dir() {
+            plan.add_dir(&target);
+            plan_tree(root, &path, &target, plan, vars, when)?;
+            continue
+        }
+
+        let contents = match String::from_utf8(fs::read(&path)?) {
+            Ok(content) => vars.render(&content).into_bytes(),
+            Err(e) => e.into_bytes(),
+        };
+
+        plan.add_file(PlannedFile::new(target, contents).executable(is_executable(&path)));
+    }
+
+    Ok(())
+}
+
+/// Whether the `when` condition for the template file `rel`, if any, holds
+fn included(rel: &Path, vars: &Vars, when: &HashMap<String, String>) -> bool {
+    let rel: Vec<String> = rel.iter().map(|c| c.to_string_lossy().into_owned()).collect();
+    let rel = rel.join("/");
+
+    when.iter()
+        .filter(|(path, _)| path.trim_matches('/') == rel)
+        .all(|(_, cond)| vars.check(cond))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
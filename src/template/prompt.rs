@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BoilError, BoilResult};
+use crate::utils::user_answer;
+use colorize::colorize;
+
+/// A question declared in a template's manifest, ie
+///
+/// ```toml
+/// [[prompts]]
+/// name = "python"
+/// message = "Python version"
+/// choices = ["3.11", "3.12"]
+/// default = "3.12"
+/// ```
+///
+/// The answer is available to the template as `{{name}}` of the prompt
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub name: String,
+    /// Question to ask, the prompt's name if not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: PromptKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// The only accepted answers, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptKind {
+    #[default]
+    String,
+    Bool,
+    Number,
+}
+
+impl Prompt {
+    /// Checks `answer` against the prompt's type and choices, returning the value
+    /// to use for it. Yes/no style answers to bool prompts become `true` or `false`
+    pub fn validate(&self, answer: &str) -> BoilResult<String> {
+        let answer = answer.trim();
+        let invalid = |reason: String| BoilError::InvalidAnswer(self.name.to_owned(), reason);
+
+        let value = match self.kind {
+            PromptKind::Bool => match answer.to_lowercase().as_str() {
+                "y" | "yes" | "true" | "1" => String::from("true"),
+                "n" | "no" | "false" | "0" => String::from("false"),
+                _ => return Err(invalid(format!("'{}' is not yes or no", answer))),
+            },
+            PromptKind::Number => match answer.parse::<f64>() {
+                Ok(_) => answer.to_owned(),
+                Err(_) => return Err(invalid(format!("'{}' is not a number", answer))),
+            },
+            PromptKind::String => answer.to_owned(),
+        };
+
+        if !self.choices.is_empty() && !self.choices.contains(&value) {
+            return Err(invalid(format!("expected one of {}", self.choices.join(", "))))
+        }
+
+        Ok(value)
+    }
+
+    /// Value used when the prompt isn't answered
+    pub fn default_value(&self) -> BoilResult<String> {
+        match (&self.default, self.kind) {
+            (Some(d), _) => self.validate(d),
+            (None, PromptKind::Bool) => Ok(String::from("false")),
+            (None, _) => Ok(self.choices.first().cloned().unwrap_or_default()),
+        }
+    }
+
+    /// Asks the prompt on the terminal until it gets a valid answer.
+    /// An empty answer takes the default
+    fn ask(&self) -> BoilResult<String> {
+        let message = self.message.as_deref().unwrap_or(&self.name);
+        let default = self.default_value()?;
+
+        let hint = match self.kind {
+            PromptKind::Bool if default == "true" => String::from("[Y/n]"),
+            PromptKind::Bool => String::from("[y/N]"),
+            _ if !self.choices.is_empty() => format!("({}) [{}]", self.choices.join("/"), default),
+            _ => format!("[{}]", default),
+        };
+
+        loop {
+            let answer = user_answer(colorize!(b->message, bFg->&hint))?;
+
+            if answer.trim().is_empty() {
+                return Ok(default)
+            }
+
+            match self.validate(&answer) {
+                Ok(value) => return Ok(value),
+                Err(e) => eprintln!("{e}"),
+            }
+        }
+    }
+}
+
+/// Answers each of `prompts`, taking answers from `set` first, then asking on the
+/// terminal when `interactive` and otherwise using the defaults. Every pair of `set`
+/// is returned along with the answers so they can be used as template variables
+pub fn answer(prompts: &[Prompt], set: &[(String, String)], interactive: bool) -> BoilResult<Vec<(String, String)>> {
+    let mut answers: Vec<(String, String)> = vec![];
+
+    for prompt in prompts.iter() {
+        let value = match set.iter().rev().find(|(k, _)| *k == prompt.name) {
+            Some((_, v)) => prompt.validate(v)?,
+            None if interactive => prompt.ask()?,
+            None => prompt.default_value()?,
+        };
+        answers.push((prompt.name.to_owned(), value));
+    }
+
+    for (key, val) in set.iter() {
+        if !prompts.iter().any(|p| p.name == *key) {
+            answers.push((key.to_owned(), val.to_owned()));
+        }
+    }
+
+    Ok(answers)
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{BoilError, BoilResult};
+use crate::project::git::{git, git_output};
+
+/// Whether `template` refers to a git repository or directory rather than
+/// the name of a template in the template directory
+pub fn is_source(template: &str) -> bool {
+    let path = Path::new(template);
+
+    template.contains("://")
+        || template.starts_with("git@")
+        || template.starts_with('.')
+        || path.is_absolute()
+        || path.components().count() > 1
+}
+
+/// Whether the local directory `path` is a git checkout or bare repository
+pub fn is_repo(path: &Path) -> bool {
+    path.join(".git").exists() || (path.join("HEAD").is_file() && path.join("objects").is_dir())
+}
+
+/// Clones the repository at `source` into the cache - or updates the existing
+/// clone - and checks out `reference` (a branch, tag or commit), or the
+/// repository's default branch when no reference is given.
+/// Returns the path of the checkout
+pub fn fetch(source: &str, reference: Option<&str>, cache: &Path) -> BoilResult<PathBuf> {
+    let dir = cache.join("templates").join(cache_key(source));
+
+    if dir.join(".git").is_dir() {
+        git(&dir, &["fetch", "--quiet", "--tags", "--force", "origin"])?;
+    } else {
+        fs::create_dir_all(cache)?;
+        git(cache, &["clone", "--quiet", "--no-checkout", "--", source, &dir.to_string_lossy()])?;
+    }
+
+    let target = match reference {
+        // Prefer the freshly fetched remote branch over a stale local one
+        Some(r) if git(&dir, &["rev-parse", "--verify", "--quiet", &format!("origin/{}", r)]).is_ok() => format!("origin/{}", r),
+        Some(r) => r.to_owned(),
+        None => String::from("origin/HEAD"),
+    };
+
+    git(&dir, &["checkout", "--quiet", "--force", "--detach", &target])?;
+
+    Ok(dir)
+}
+
+/// The existing clone of `source` in the cache, without fetching or checking anything
+/// out. With a `reference` the clone has to be checked out at it already
+pub fn cached(source: &str, reference: Option<&str>, cache: &Path) -> BoilResult<PathBuf> {
+    let dir = cache.join("templates").join(cache_key(source));
+    let not_cached = || BoilError::TemplateNotCached(source.to_owned());
+
+    if !dir.join(".git").is_dir() {
+        return Err(not_cached())
+    }
+
+    if let Some(r) = reference {
+        let head = git_output(&dir, &["rev-parse", "HEAD"])?;
+        let matches = [format!("origin/{}", r), r.to_owned()].iter().any(|target| {
+            git_output(&dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", target)])
+                .is_ok_and(|rev| rev == head)
+        });
+
+        if !matches {
+            return Err(not_cached())
+        }
+    }
+
+    Ok(dir)
+}
+
+/// Readable and unique directory name for a source, ie `svc-3f2a...`
+fn cache_key(source: &str) -> String {
+    // FNV-1a, which unlike std's hasher is stable between releases
+    let hash = source.bytes().fold(0xcbf29ce484222325_u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+
+    let name = source
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".git");
+
+    format!("{}-{:016x}", name, hash)
+}
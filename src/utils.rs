@@ -15,17 +15,38 @@ macro_rules! capitalize {
 
 
 pub fn user_input(msg: String) -> BoilResult<bool> {
+    Ok(user_answer(msg)?.trim() == "y")
+}
+
+/// Prints `msg` and reads a line of input, without the trailing newline
+pub fn user_answer(msg: String) -> BoilResult<String> {
     let mut input = String::new();
     print!("{} ", msg);
     io::stdout().flush()?;
     io::stdin().read_line(&mut input)?;
 
-    if input.as_str().trim() == "y" {
-        Ok(true)
-    } else {
-        Ok(false)
-    }
-    
+    Ok(input.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Seconds since the unix epoch as a UTC `YYYY-MM-DD HH:MM:SS`
+pub fn utc_datetime(secs: u64) -> String {
+    // Days since epoch to a civil date (Howard Hinnant's algorithm)
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let time = secs % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year, month, day, time / 3600, time / 60 % 60, time % 60
+    )
 }
 
 pub(crate) use capitalize;